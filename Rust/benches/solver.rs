@@ -0,0 +1,90 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use genetic_routing_problem::{file_io, Problem, Solver, SolverConfig};
+
+const GENERATIONS: usize = 20;
+const ORDERS: usize = 1000;
+
+fn instances() -> Vec<(String, Problem)> {
+    (1..=23).map(|i| {
+        let name = format!("p{:02}", i);
+        let input = file_io::read_to_string(format!("src/data/{}", name)).expect("missing benchmark instance");
+        let problem = file_io::parse_cordeau(&input).expect("invalid benchmark instance");
+        (name, problem)
+    }).collect()
+}
+
+// Random customer orders with evenly spaced separators, like the initial population
+fn random_orders(problem: &Problem, rng: &mut StdRng) -> Vec<Vec<i64>> {
+    let n_customers = problem.customers().len();
+    let total_vehicles = problem.total_vehicles();
+    (0..ORDERS).map(|_| {
+        let mut order: Vec<i64> = (1..=n_customers as i64).collect();
+        order.shuffle(rng);
+        let step = n_customers/total_vehicles;
+        for i in (1..total_vehicles).rev() {
+            order.insert(step*i, 0);
+        }
+        order
+    }).collect()
+}
+
+// Fitness evaluations per second
+fn evaluate_orders(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut group = c.benchmark_group("evaluate");
+    group.throughput(Throughput::Elements(ORDERS as u64));
+    for (name, problem) in instances() {
+        let orders = random_orders(&problem, &mut rng);
+        group.bench_with_input(BenchmarkId::from_parameter(&name), &orders, |b, orders| {
+            b.iter(|| orders.iter().map(|o| Solver::evaluate(&problem, o).total_cost).sum::<f64>())
+        });
+    }
+    group.finish();
+}
+
+// Legs between consecutive customers of the orders, read from the distance matrix or computed again each time
+// as before it existed. The throughput is in orders per second.
+fn leg_distances(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut group = c.benchmark_group("distance");
+    group.throughput(Throughput::Elements(ORDERS as u64));
+    for (name, problem) in instances() {
+        let orders: Vec<Vec<i64>> = random_orders(&problem, &mut rng).into_iter()
+            .map(|o| o.into_iter().filter(|&c| c != 0).collect())
+            .collect();
+        group.bench_with_input(BenchmarkId::new("matrix", &name), &orders, |b, orders| {
+            b.iter(|| orders.iter().map(|o| {
+                o.windows(2).map(|w| problem.distance(Problem::customer_node(w[0]), Problem::customer_node(w[1]))).sum::<f64>()
+            }).sum::<f64>())
+        });
+        group.bench_with_input(BenchmarkId::new("sqrt", &name), &orders, |b, orders| {
+            b.iter(|| orders.iter().map(|o| {
+                o.windows(2).map(|w| {
+                    let (a, b) = (problem.customer(w[0]), problem.customer(w[1]));
+                    a.dist(b.x(), b.y())
+                }).sum::<f64>()
+            }).sum::<f64>())
+        });
+    }
+    group.finish();
+}
+
+// Short seeded runs, the throughput is in generations per second
+fn solve_instances(c: &mut Criterion) {
+    let config = SolverConfig{generations: GENERATIONS, seed: Some(0), verbose: false, ..SolverConfig::default()};
+    let mut group = c.benchmark_group("solve");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(GENERATIONS as u64));
+    for (name, problem) in instances() {
+        group.bench_with_input(BenchmarkId::from_parameter(&name), &problem, |b, problem| {
+            b.iter(|| Solver::solve(problem, &config))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, evaluate_orders, leg_distances, solve_instances);
+criterion_main!(benches);
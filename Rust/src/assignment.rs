@@ -0,0 +1,86 @@
+use rand::prelude::*;
+use std::cmp::Ordering;
+
+use crate::config::Assignment;
+use crate::world::Problem;
+
+fn compare(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+// Depots that have vehicles, from the nearest to the furthest from customer c
+pub fn depots_by_distance(problem: &Problem, c: i64) -> Vec<(usize, f64)> {
+    let node = Problem::customer_node(c);
+    let mut depots: Vec<(usize, f64)> = (0..problem.depots().len())
+        .filter(|&d| problem.depots()[d].vehicles() > 0)
+        .map(|d| (d, problem.distance(node, problem.depot_node(d))))
+        .collect();
+    depots.sort_by(|a, b| compare(a.1, b.1));
+    depots
+}
+
+// Depots at most margin times further from customer c than its nearest depot, the nearest first.
+// A customer with several of them is a borderline customer.
+pub fn close_depots(problem: &Problem, c: i64, margin: f64) -> Vec<usize> {
+    let depots = depots_by_distance(problem, c);
+    let nearest = depots.first().map_or(0.0, |d| d.1);
+    depots.iter().filter(|d| d.1 <= (1.0 + margin)*nearest).map(|d| d.0).collect()
+}
+
+// Borderline customers with their close depots
+pub fn borderline_customers(problem: &Problem, margin: f64) -> Vec<(i64, Vec<usize>)> {
+    (1..=problem.customers().len() as i64)
+        .map(|c| (c, close_depots(problem, c, margin)))
+        .filter(|(_, depots)| depots.len() > 1)
+        .collect()
+}
+
+// Customers of each depot. The random assignment has no rule of its own and gives customers to their nearest depot.
+pub fn assign<R: Rng>(problem: &Problem, assignment: Assignment, rng: &mut R) -> Vec<Vec<i64>> {
+    let mut groups = vec![Vec::new(); problem.depots().len()];
+    let customers = 1..=problem.customers().len() as i64;
+    match assignment {
+        Assignment::Random | Assignment::Nearest => {
+            for c in customers {
+                groups[depots_by_distance(problem, c)[0].0].push(c);
+            }
+        },
+        // Depots are drawn with a probability inversely proportional to their squared distance
+        Assignment::Probabilistic => {
+            for c in customers {
+                let depots = depots_by_distance(problem, c);
+                let weights: Vec<f64> = depots.iter().map(|d| 1.0/(d.1*d.1).max(f64::MIN_POSITIVE)).collect();
+                let mut draw = rng.gen::<f64>()*weights.iter().sum::<f64>();
+                let mut chosen = depots[depots.len() - 1].0;
+                for (d, w) in depots.iter().zip(&weights) {
+                    if draw < *w {
+                        chosen = d.0;
+                        break;
+                    }
+                    draw = draw - w;
+                }
+                groups[chosen].push(c);
+            }
+        },
+        // Customers whose nearest depot is much closer than the others pick first, and go to the nearest depot
+        // that still has the capacity to serve them, or to their nearest one when no depot has
+        Assignment::Capacity => {
+            let mut left: Vec<i64> = problem.depots().iter()
+                .map(|d| d.fleet().iter().map(|t| t.max_load()*t.count()).sum())
+                .collect();
+            let mut order: Vec<(i64, Vec<(usize, f64)>)> = customers.map(|c| (c, depots_by_distance(problem, c))).collect();
+            let regret = |depots: &Vec<(usize, f64)>| if depots.len() > 1 {depots[1].1 - depots[0].1} else {f64::INFINITY};
+            order.sort_by(|a, b| compare(regret(&b.1), regret(&a.1)));
+            for (c, depots) in order {
+                let load = problem.customer(c).load();
+                let chosen = depots.iter().find(|d| left[d.0] >= load).unwrap_or(&depots[0]).0;
+                left[chosen] = left[chosen] - load;
+                groups[chosen].push(c);
+            }
+            for group in groups.iter_mut() {
+                group.sort_unstable();
+            }
+        },
+    }
+    groups
+}
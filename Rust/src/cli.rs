@@ -0,0 +1,246 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use genetic_routing_problem::{ConfigError, Objective, Rounding, SolverConfig};
+
+pub const USAGE: &str = "\
+Usage:
+    genetic_routing_problem solve <instance> [options]
+    genetic_routing_problem batch <instance>... [options]
+    genetic_routing_problem validate <instance>... [options]
+    genetic_routing_problem validate <instance> --solution <file> [options]
+    genetic_routing_problem help
+
+Options:
+    -o, --output-dir <dir>      Directory for result and graph files (default: results)
+    -s, --solution <file>       Check a result file against the instance and print a JSON report,
+                                the exit status is 1 when the solution is not valid (validate only)
+    -f, --fleet <file>          Replace the vehicles of the depots with the vehicle types of a fleet file,
+                                one 'depot count max_duration max_load fixed_cost distance_cost' line per type
+    -c, --config <file>         Load the solver configuration from a TOML or JSON file,
+                                the options below override the values it contains
+        --pop-size <n>          Number of individuals kept each generation (default: 50)
+        --generations <n>       Maximum number of generations (default: 50000)
+        --children <n>          Children produced per individual each generation (default: 7)
+        --convergence <n>       Stop after this many generations without improvement (default: 10000)
+        --default-max-duration <n>
+                                Route duration limit for depots that have none in the instance
+        --objective <name>      Minimised objective : total, minmax or weighted (default: total)
+        --rounding <policy>     Rounding of distances : exact, round or truncate (default: exact)
+        --seed <n>              Seed of the random number generator, for reproducible runs
+        --pareto                Multi-objective run with NSGA-II, writes the Pareto front over the
+                                pareto_objectives of the configuration (default: cost, vehicles, imbalance)
+    -q, --quiet                 Don't print statistics for every generation
+    -h, --help                  Print this message";
+
+pub enum Command {
+    Solve(RunArgs),
+    Batch(RunArgs),
+    Validate(RunArgs),
+    Help,
+}
+
+pub struct RunArgs {
+    pub instances: Vec<PathBuf>,
+    pub output_dir: PathBuf,
+    pub solution: Option<PathBuf>,
+    pub fleet: Option<PathBuf>,
+    pub config: SolverConfig,
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    MissingCommand,
+    UnknownCommand(String),
+    UnknownOption(String),
+    MissingValue(String),
+    InvalidValue { option: String, value: String, expected: &'static str },
+    MissingInstance,
+    TooManyInstances(usize),
+    TooManySolutionInstances(usize),
+    Config(ConfigError),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::MissingCommand => write!(f, "no command given"),
+            CliError::UnknownCommand(c) => write!(f, "unknown command '{}'", c),
+            CliError::UnknownOption(o) => write!(f, "unknown option '{}'", o),
+            CliError::MissingValue(o) => write!(f, "option '{}' expects a value", o),
+            CliError::InvalidValue { option, value, expected } => {
+                write!(f, "invalid value '{}' for '{}': expected {}", value, option, expected)
+            },
+            CliError::MissingInstance => write!(f, "no instance file given"),
+            CliError::TooManyInstances(n) => write!(f, "'solve' takes a single instance but {} were given, use 'batch' instead", n),
+            CliError::TooManySolutionInstances(n) => write!(f, "'--solution' is checked against a single instance but {} were given", n),
+            CliError::Config(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+pub fn parse(args: &[String]) -> Result<Command, CliError> {
+    let (command, rest) = match args.split_first() {
+        None => return Err(CliError::MissingCommand),
+        Some((c, rest)) => (c.as_str(), rest),
+    };
+    match command {
+        "help" | "-h" | "--help" => Ok(Command::Help),
+        "solve" => {
+            let run = match parse_run_args(rest, false)? {
+                None => return Ok(Command::Help),
+                Some(run) => run,
+            };
+            if run.instances.len() > 1 {
+                return Err(CliError::TooManyInstances(run.instances.len()))
+            }
+            Ok(Command::Solve(run))
+        },
+        "batch" => Ok(parse_run_args(rest, false)?.map_or(Command::Help, Command::Batch)),
+        "validate" => {
+            let run = match parse_run_args(rest, true)? {
+                None => return Ok(Command::Help),
+                Some(run) => run,
+            };
+            if run.solution.is_some() && run.instances.len() > 1 {
+                return Err(CliError::TooManySolutionInstances(run.instances.len()))
+            }
+            Ok(Command::Validate(run))
+        },
+        other if other.starts_with('-') => Err(CliError::UnknownOption(other.to_string())),
+        other => Err(CliError::UnknownCommand(other.to_string())),
+    }
+}
+
+// Returns None when help was requested, --solution is only known to the commands that accept it
+fn parse_run_args(args: &[String], accepts_solution: bool) -> Result<Option<RunArgs>, CliError> {
+    let mut instances = Vec::new();
+    let mut output_dir = PathBuf::from("results");
+    let mut config_path = None;
+    let mut solution = None;
+    let mut fleet = None;
+    let mut pop_size = None;
+    let mut generations = None;
+    let mut children = None;
+    let mut convergence_time = None;
+    let mut default_max_duration = None;
+    let mut seed = None;
+    let mut rounding = None;
+    let mut objective = None;
+    let mut quiet = false;
+    let mut pareto = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        if !arg.starts_with('-') || arg == "-" {
+            instances.push(PathBuf::from(arg));
+            i = i + 1;
+            continue;
+        }
+        if arg == "-h" || arg == "--help" {
+            return Ok(None)
+        }
+        if arg == "-q" || arg == "--quiet" {
+            quiet = true;
+            i = i + 1;
+            continue;
+        }
+        if arg == "--pareto" {
+            pareto = true;
+            i = i + 1;
+            continue;
+        }
+
+        // Both "--opt value" and "--opt=value" are accepted
+        let (name, inline_value) = match arg.find('=') {
+            Some(idx) if arg.starts_with("--") => (&arg[..idx], Some(arg[idx + 1..].to_string())),
+            _ => (arg, None),
+        };
+        let mut value = || -> Result<String, CliError> {
+            match &inline_value {
+                Some(v) => Ok(v.clone()),
+                None => {
+                    i = i + 1;
+                    args.get(i).cloned().ok_or_else(|| CliError::MissingValue(name.to_string()))
+                },
+            }
+        };
+
+        match name {
+            "-o" | "--output-dir" => output_dir = PathBuf::from(value()?),
+            "-c" | "--config" => config_path = Some(PathBuf::from(value()?)),
+            "-f" | "--fleet" => fleet = Some(PathBuf::from(value()?)),
+            "-s" | "--solution" if accepts_solution => solution = Some(PathBuf::from(value()?)),
+            "--pop-size" => pop_size = Some(parse_number(name, &value()?)?),
+            "--generations" => generations = Some(parse_number(name, &value()?)?),
+            "--children" => children = Some(parse_number(name, &value()?)?),
+            "--convergence" => convergence_time = Some(parse_number(name, &value()?)?),
+            "--default-max-duration" => default_max_duration = Some(parse_number(name, &value()?)?),
+            "--seed" => seed = Some(parse_number(name, &value()?)?),
+            "--rounding" => rounding = Some(parse_rounding(name, &value()?)?),
+            "--objective" => objective = Some(parse_objective(name, &value()?)?),
+            _ => return Err(CliError::UnknownOption(name.to_string())),
+        }
+        i = i + 1;
+    }
+
+    if instances.is_empty() {
+        return Err(CliError::MissingInstance)
+    }
+
+    // Options given on the command line take precedence over the config file
+    let mut config = match config_path {
+        None => SolverConfig::default(),
+        Some(path) => SolverConfig::from_file(path).map_err(CliError::Config)?,
+    };
+    config.pop_size = pop_size.unwrap_or(config.pop_size);
+    config.generations = generations.unwrap_or(config.generations);
+    config.children = children.unwrap_or(config.children);
+    config.convergence_time = convergence_time.unwrap_or(config.convergence_time);
+    config.default_max_duration = default_max_duration.or(config.default_max_duration);
+    config.seed = seed.or(config.seed);
+    config.distance_rounding = rounding.unwrap_or(config.distance_rounding);
+    config.objective = objective.unwrap_or(config.objective);
+    config.verbose = config.verbose && !quiet;
+    config.pareto = config.pareto || pareto;
+    config.validate().map_err(CliError::Config)?;
+    Ok(Some(RunArgs{instances, output_dir, solution, fleet, config}))
+}
+
+fn parse_number<T: FromStr>(option: &str, value: &str) -> Result<T, CliError> {
+    value.parse::<T>().map_err(|_| CliError::InvalidValue {
+        option: option.to_string(),
+        value: value.to_string(),
+        expected: "a non-negative integer",
+    })
+}
+
+fn parse_rounding(option: &str, value: &str) -> Result<Rounding, CliError> {
+    match value {
+        "exact" => Ok(Rounding::Exact),
+        "round" => Ok(Rounding::Round),
+        "truncate" => Ok(Rounding::Truncate),
+        _ => Err(CliError::InvalidValue {
+            option: option.to_string(),
+            value: value.to_string(),
+            expected: "one of exact, round or truncate",
+        }),
+    }
+}
+
+fn parse_objective(option: &str, value: &str) -> Result<Objective, CliError> {
+    match value {
+        "total" => Ok(Objective::Total),
+        "minmax" => Ok(Objective::MinMax),
+        "weighted" => Ok(Objective::Weighted),
+        _ => Err(CliError::InvalidValue {
+            option: option.to_string(),
+            value: value.to_string(),
+            expected: "one of total, minmax or weighted",
+        }),
+    }
+}
@@ -0,0 +1,427 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::file_io;
+use crate::solution::Evaluation;
+use crate::world::Rounding;
+
+const FRAC_TOLERANCE: f64 = 1e-6;
+
+// What the genetic algorithm minimises : the total cost of the routes, the duration of the longest route,
+// or a mix of both weighted by SolverConfig::objective_weight
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Objective {
+    #[default]
+    Total,
+    MinMax,
+    Weighted,
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Objective::Total => write!(f, "total"),
+            Objective::MinMax => write!(f, "minmax"),
+            Objective::Weighted => write!(f, "weighted"),
+        }
+    }
+}
+
+// How a genome encodes its routes : a zero between the routes of consecutive vehicles, or a giant tour per depot
+// with a zero between consecutive depots, that is split into the routes of the depot's vehicles
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    #[default]
+    Separators,
+    GiantTour,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Encoding::Separators => write!(f, "separators"),
+            Encoding::GiantTour => write!(f, "giant_tour"),
+        }
+    }
+}
+
+// How customers are given to depots : random genomes leave it to chance while the heuristics use the nearest depot,
+// or every genome uses the nearest depot, a depot drawn with a probability that decreases with its distance,
+// or the nearest depot that still has the capacity to serve the customer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Assignment {
+    #[default]
+    Random,
+    Nearest,
+    Probabilistic,
+    Capacity,
+}
+
+impl fmt::Display for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Assignment::Random => write!(f, "random"),
+            Assignment::Nearest => write!(f, "nearest"),
+            Assignment::Probabilistic => write!(f, "probabilistic"),
+            Assignment::Capacity => write!(f, "capacity"),
+        }
+    }
+}
+
+// How the probabilities of the mutation and crossover operators change during a run : fixed to their fractions,
+// or adapted to the fitness gains of the children they make by probability matching or adaptive pursuit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperatorSelection {
+    #[default]
+    Fixed,
+    Matching,
+    Pursuit,
+}
+
+impl fmt::Display for OperatorSelection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OperatorSelection::Fixed => write!(f, "fixed"),
+            OperatorSelection::Matching => write!(f, "matching"),
+            OperatorSelection::Pursuit => write!(f, "pursuit"),
+        }
+    }
+}
+
+// Local search strategy : apply the first improving move found around a customer, or the best one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Improvement {
+    #[default]
+    First,
+    Best,
+}
+
+impl fmt::Display for Improvement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Improvement::First => write!(f, "first"),
+            Improvement::Best => write!(f, "best"),
+        }
+    }
+}
+
+// Objectives of the multi-objective mode, all minimised : total cost, number of vehicles with a non empty route,
+// difference of duration between the longest and shortest non empty routes, duration of the longest route
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParetoObjective {
+    Cost,
+    Vehicles,
+    Imbalance,
+    Longest,
+}
+
+impl ParetoObjective {
+    pub fn value(self, evaluation: &Evaluation) -> f64 {
+        match self {
+            ParetoObjective::Cost => evaluation.total_cost,
+            ParetoObjective::Vehicles => evaluation.vehicles_used as f64,
+            ParetoObjective::Imbalance => evaluation.imbalance,
+            ParetoObjective::Longest => evaluation.longest_route,
+        }
+    }
+}
+
+impl fmt::Display for ParetoObjective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParetoObjective::Cost => write!(f, "cost"),
+            ParetoObjective::Vehicles => write!(f, "vehicles"),
+            ParetoObjective::Imbalance => write!(f, "imbalance"),
+            ParetoObjective::Longest => write!(f, "longest"),
+        }
+    }
+}
+
+// Every tunable of the genetic algorithm. Missing keys in a config file take their default value.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SolverConfig {
+    // General
+    pub pop_size: usize,
+    pub generations: usize,
+    pub children: usize,
+    pub convergence_time: usize,
+
+    // Initial population, the part that isn't built by the savings, sweep or nearest neighbour heuristics is random
+    pub frac_savings: f64,
+    pub frac_sweep: f64,
+    pub frac_nearest: f64,
+
+    // Depots
+    pub assignment: Assignment,
+    pub borderline_margin: f64, // A customer is close to the depots at most 1 + margin times further than its nearest one
+
+    // Selection
+    pub elites: usize,
+    pub pool_size: usize,
+    pub tournament_size: usize, // 1 is random, higher up to pop_size is higher pressure
+    pub selection_pressure: f64, // Higher = closer to deterministic, should be between 0 and 1
+
+    // Mutation
+    pub prob_mutation: f64,
+    pub frac_insert: f64,
+    pub frac_swap: f64,
+    pub frac_scramble: f64,
+    pub frac_inter_depot: f64, // Moves a borderline customer to the cheapest place in a route of another close depot
+
+    // Crossover
+    pub prob_crossover: f64,
+    pub frac_order1: f64,
+    pub frac_pmx: f64,
+    pub frac_edge_recomb: f64,
+    pub frac_bcrc: f64, // Best cost route crossover, reinserts the customers of a route of the other parent
+
+    // Operator selection, the fractions above are the starting probabilities of adapted operators
+    pub operator_selection: OperatorSelection,
+    pub adaptation_rate: f64, // How fast the qualities and, with pursuit, the probabilities follow the rewards
+    pub min_operator_prob: f64, // Lowest probability of an operator whose fraction isn't zero
+
+    // Local search
+    pub prob_education: f64, // Probability that a child is improved by local search
+    pub improvement: Improvement,
+    pub ls_neighbours: usize, // Moves only bring a customer next to one of its ls_neighbours nearest customers
+
+    // Fitness
+    pub encoding: Encoding,
+    pub objective: Objective,
+    pub objective_weight: f64, // Weight of the longest route in the weighted objective, the total cost gets 1 - weight
+    pub distance_rounding: Rounding,
+    pub repaired: bool,
+    pub load_weight: f64, // Weight of load excess in the fitness
+    pub duration_weight: f64, // Weight of duration excess in the fitness
+    pub lateness_weight: f64, // Weight of time window lateness in the fitness
+    // Adapt the load and duration weights every generation, towards target_feasible of the population respecting each
+    // constraint, by penalty_factor at a time
+    pub adaptive_penalty: bool,
+    pub target_feasible: f64,
+    pub penalty_factor: f64,
+    pub penalty_convergence: usize, // Generations without progress after which the weights stop shrinking
+    // Route duration limit for depots that have none in the instance
+    pub default_max_duration: Option<i64>,
+
+    // Multi-objective
+    pub pareto: bool, // Rank individuals with NSGA-II over pareto_objectives and return the Pareto front
+    pub pareto_objectives: Vec<ParetoObjective>,
+
+    // Master seed of the run, a random one is drawn when unset
+    pub seed: Option<u64>,
+
+    // Print statistics for every generation
+    pub verbose: bool,
+}
+
+impl Default for SolverConfig {
+    fn default() -> SolverConfig {
+        SolverConfig{
+            pop_size: 50,
+            generations: 50_000,
+            children: 7,
+            convergence_time: 10_000,
+
+            frac_savings: 0.0,
+            frac_sweep: 0.0,
+            frac_nearest: 0.0,
+
+            assignment: Assignment::Random,
+            borderline_margin: 0.5,
+
+            elites: 2,
+            pool_size: 10,
+            tournament_size: 8,
+            selection_pressure: 0.9,
+
+            prob_mutation: 0.2,
+            frac_insert: 0.4,
+            frac_swap: 0.3,
+            frac_scramble: 0.3,
+            frac_inter_depot: 0.0,
+
+            prob_crossover: 0.9,
+            frac_order1: 0.1,
+            frac_pmx: 0.9,
+            frac_edge_recomb: 0.0,
+            frac_bcrc: 0.0,
+
+            operator_selection: OperatorSelection::Fixed,
+            adaptation_rate: 0.3,
+            min_operator_prob: 0.05,
+
+            prob_education: 0.0,
+            improvement: Improvement::First,
+            ls_neighbours: 20,
+
+            encoding: Encoding::Separators,
+            objective: Objective::Total,
+            objective_weight: 0.5,
+            distance_rounding: Rounding::Exact,
+            repaired: true,
+            load_weight: 1000.0,
+            duration_weight: 1000.0,
+            lateness_weight: 1000.0,
+            adaptive_penalty: false,
+            target_feasible: 0.2,
+            penalty_factor: 1.2,
+            penalty_convergence: 10,
+            default_max_duration: None,
+
+            pareto: false,
+            pareto_objectives: vec![ParetoObjective::Cost, ParetoObjective::Vehicles, ParetoObjective::Imbalance],
+
+            seed: None,
+
+            verbose: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(String, std::io::Error),
+    Parse(String, String),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "couldn't read config {}: {}", path, e),
+            ConfigError::Parse(path, e) => write!(f, "couldn't parse config {}: {}", path, e),
+            ConfigError::Invalid(e) => write!(f, "invalid configuration: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl SolverConfig {
+    // The format is picked from the extension : .json is read as JSON, anything else as TOML.
+    // The result is not validated so that it can still be overridden, call validate() before use.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<SolverConfig, ConfigError> {
+        let path = path.as_ref();
+        let display = path.display().to_string();
+        let text = file_io::read_to_string(path).map_err(|e| ConfigError::Io(display.clone(), e))?;
+        let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        if is_json {
+            serde_json::from_str(&text).map_err(|e| ConfigError::Parse(display, e.to_string()))
+        }
+        else {
+            toml::from_str(&text).map_err(|e| ConfigError::Parse(display, e.to_string()))
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |s: String| Err(ConfigError::Invalid(s));
+
+        // The selection takes its pool out of the population and keeps the elites
+        if self.pool_size == 0 {
+            return invalid(String::from("pool_size must be at least 1"))
+        }
+        if self.pop_size < self.elites + self.pool_size {
+            return invalid(format!("pop_size ({}) must be at least elites + pool_size ({})", self.pop_size, self.elites + self.pool_size))
+        }
+        if self.tournament_size == 0 || self.tournament_size > self.pop_size {
+            return invalid(format!("tournament_size ({}) must be between 1 and pop_size ({})", self.tournament_size, self.pop_size))
+        }
+        if self.children == 0 {
+            return invalid(String::from("children must be at least 1"))
+        }
+        if self.generations == 0 {
+            return invalid(String::from("generations must be at least 1"))
+        }
+        if self.load_weight.is_nan() || self.load_weight < 0.0 {
+            return invalid(format!("load_weight must be non-negative, got {}", self.load_weight))
+        }
+        if self.duration_weight.is_nan() || self.duration_weight < 0.0 {
+            return invalid(format!("duration_weight must be non-negative, got {}", self.duration_weight))
+        }
+        if self.penalty_factor.is_nan() || self.penalty_factor < 1.0 {
+            return invalid(format!("penalty_factor must be at least 1, got {}", self.penalty_factor))
+        }
+        if self.penalty_convergence == 0 {
+            return invalid(String::from("penalty_convergence must be at least 1"))
+        }
+        if !(0.0..=1.0).contains(&self.objective_weight) {
+            return invalid(format!("objective_weight must be between 0 and 1, got {}", self.objective_weight))
+        }
+        if self.pareto {
+            if self.pareto_objectives.len() < 2 {
+                return invalid(String::from("pareto_objectives must contain at least two objectives"))
+            }
+            for (i, o) in self.pareto_objectives.iter().enumerate() {
+                if self.pareto_objectives[..i].contains(o) {
+                    return invalid(format!("pareto_objectives contains {} twice", o))
+                }
+            }
+        }
+        if self.lateness_weight.is_nan() || self.lateness_weight < 0.0 {
+            return invalid(format!("lateness_weight must be non-negative, got {}", self.lateness_weight))
+        }
+        if self.borderline_margin.is_nan() || self.borderline_margin < 0.0 {
+            return invalid(format!("borderline_margin must be non-negative, got {}", self.borderline_margin))
+        }
+        if self.ls_neighbours == 0 {
+            return invalid(String::from("ls_neighbours must be at least 1"))
+        }
+        if let Some(d) = self.default_max_duration {
+            if d <= 0 {
+                return invalid(format!("default_max_duration must be positive, got {}", d))
+            }
+        }
+
+        let probabilities = [
+            ("frac_savings", self.frac_savings),
+            ("frac_sweep", self.frac_sweep),
+            ("frac_nearest", self.frac_nearest),
+            ("selection_pressure", self.selection_pressure),
+            ("prob_mutation", self.prob_mutation),
+            ("frac_insert", self.frac_insert),
+            ("frac_swap", self.frac_swap),
+            ("frac_scramble", self.frac_scramble),
+            ("frac_inter_depot", self.frac_inter_depot),
+            ("prob_crossover", self.prob_crossover),
+            ("frac_order1", self.frac_order1),
+            ("frac_pmx", self.frac_pmx),
+            ("frac_edge_recomb", self.frac_edge_recomb),
+            ("frac_bcrc", self.frac_bcrc),
+            ("adaptation_rate", self.adaptation_rate),
+            ("min_operator_prob", self.min_operator_prob),
+            ("target_feasible", self.target_feasible),
+            ("prob_education", self.prob_education),
+        ];
+        for &(name, p) in probabilities.iter() {
+            if !(0.0..=1.0).contains(&p) {
+                return invalid(format!("{} must be between 0 and 1, got {}", name, p))
+            }
+        }
+
+        if self.operator_selection != OperatorSelection::Fixed && self.min_operator_prob >= 0.25 {
+            return invalid(format!("min_operator_prob must be below 0.25 so that four operators can share the rest, got {}", self.min_operator_prob))
+        }
+        let seeded_sum = self.frac_savings + self.frac_sweep + self.frac_nearest;
+        if seeded_sum > 1.0 + FRAC_TOLERANCE {
+            return invalid(format!("frac_savings + frac_sweep + frac_nearest must be at most 1, got {}", seeded_sum))
+        }
+        let mutation_sum = self.frac_insert + self.frac_swap + self.frac_scramble + self.frac_inter_depot;
+        if (mutation_sum - 1.0).abs() > FRAC_TOLERANCE {
+            return invalid(format!("frac_insert + frac_swap + frac_scramble + frac_inter_depot must sum to 1, got {}", mutation_sum))
+        }
+        let crossover_sum = self.frac_order1 + self.frac_pmx + self.frac_edge_recomb + self.frac_bcrc;
+        if (crossover_sum - 1.0).abs() > FRAC_TOLERANCE {
+            return invalid(format!("frac_order1 + frac_pmx + frac_edge_recomb + frac_bcrc must sum to 1, got {}", crossover_sum))
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+use rand::prelude::*;
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+
+use crate::assignment::assign;
+use crate::config::{Assignment, SolverConfig};
+use crate::decoder::{evaluate_route, RouteBuilder};
+use crate::genome::Genome;
+use crate::world::Problem;
+
+// Savings are scaled by a random factor up to this far from 1, so that the individuals they build differ
+const SAVINGS_NOISE: f64 = 0.1;
+
+#[derive(Clone, Copy)]
+enum Heuristic {
+    Savings,
+    Sweep,
+    NearestNeighbour,
+}
+
+// The first population : config.frac_savings, frac_sweep and frac_nearest of it are built by the heuristics,
+// and the rest is random. Customers go to depots according to config.assignment.
+pub fn initial_population<R: Rng>(problem: &Problem, config: &SolverConfig, rng: &mut R) -> Vec<Genome> {
+    let mut pop = Vec::with_capacity(config.pop_size);
+    let seeded = [
+        (config.frac_savings, Heuristic::Savings),
+        (config.frac_sweep, Heuristic::Sweep),
+        (config.frac_nearest, Heuristic::NearestNeighbour),
+    ];
+    for &(frac, heuristic) in seeded.iter() {
+        let count = (frac*config.pop_size as f64).round() as usize;
+        for _ in 0..count {
+            if pop.len() < config.pop_size {
+                pop.push(construct(heuristic, problem, config, rng));
+            }
+        }
+    }
+    while pop.len() < config.pop_size {
+        let genome = match config.assignment {
+            Assignment::Random => Genome::random(problem, config, rng),
+            _ => assigned_random(problem, config, rng),
+        };
+        pop.push(genome);
+    }
+    pop
+}
+
+// The customers of each depot are shuffled and spread evenly over its vehicles
+fn assigned_random<R: Rng>(problem: &Problem, config: &SolverConfig, rng: &mut R) -> Genome {
+    let mut routes = Vec::with_capacity(problem.total_vehicles());
+    for (depot, mut customers) in assign(problem, config.assignment, rng).into_iter().enumerate() {
+        let vehicles = problem.depots()[depot].vehicles() as usize;
+        customers.shuffle(rng);
+        let step = customers.len()/vehicles.max(1);
+        for v in 0..vehicles {
+            let end = if v + 1 == vehicles {customers.len()} else {step*(v + 1)};
+            routes.push(customers[step*v..end].to_vec());
+        }
+    }
+    Genome::from_routes(routes.join(&0), problem, config)
+}
+
+// Customers are assigned to depots, whose routes are then built by the heuristic
+fn construct<R: Rng>(heuristic: Heuristic, problem: &Problem, config: &SolverConfig, rng: &mut R) -> Genome {
+    let mut routes = Vec::with_capacity(problem.total_vehicles());
+    for (depot, customers) in assign(problem, config.assignment, rng).iter().enumerate() {
+        let vehicles = problem.depots()[depot].vehicles() as usize;
+        if vehicles == 0 {
+            continue;
+        }
+        let depot_routes = match heuristic {
+            Heuristic::Savings => savings(problem, depot, customers, rng),
+            Heuristic::Sweep => sweep(problem, depot, customers, rng),
+            Heuristic::NearestNeighbour => nearest_neighbour(problem, depot, customers, rng),
+        };
+        routes.extend(fit_fleet(problem, depot, depot_routes));
+    }
+    Genome::from_routes(routes.join(&0), problem, config)
+}
+
+// One route per vehicle of the depot. While there are more routes than vehicles, the smallest one is dissolved
+// and its customers go one at a time to their cheapest place in the other routes, among the places that keep
+// their route feasible if there are any.
+fn fit_fleet(problem: &Problem, depot: usize, mut routes: Vec<Vec<i64>>) -> Vec<Vec<i64>> {
+    let vehicles = problem.depots()[depot].vehicles() as usize;
+    while routes.len() > vehicles {
+        let smallest = (0..routes.len()).min_by_key(|&r| routes[r].len()).unwrap();
+        let extra = routes.remove(smallest);
+        for c in extra {
+            // Feasibility first, then the added cost
+            let mut best: Option<(bool, f64, usize, usize)> = None;
+            for (r, route) in routes.iter().enumerate() {
+                let vehicle = vehicle_of(problem, depot, r);
+                let before = evaluate_route(problem, depot, vehicle, route).cost;
+                for at in 0..=route.len() {
+                    let mut trial = route.clone();
+                    trial.insert(at, c);
+                    let stats = evaluate_route(problem, depot, vehicle, &trial);
+                    let place = (stats.violations.is_empty(), stats.cost - before, r, at);
+                    let better = match best {
+                        None => true,
+                        Some((ok, added, _, _)) => (place.0 && !ok) || (place.0 == ok && place.1 < added),
+                    };
+                    if better {
+                        best = Some(place);
+                    }
+                }
+            }
+            let (_, _, r, at) = best.unwrap();
+            routes[r].insert(at, c);
+        }
+    }
+    routes.resize(vehicles, Vec::new());
+    routes
+}
+
+fn compare(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+// Index of the vehicle a new route goes to, the last one takes any route beyond the fleet
+fn vehicle_of(problem: &Problem, depot: usize, route: usize) -> usize {
+    route.min((problem.depots()[depot].vehicles() as usize).saturating_sub(1))
+}
+
+fn feasible(route: &RouteBuilder) -> bool {
+    route.stats().violations.is_empty()
+}
+
+// Clarke and Wright's parallel savings : every customer starts in its own route, and routes are joined end to end
+// by decreasing saving while the joined route is feasible for the depot's first vehicle
+fn savings<R: Rng>(problem: &Problem, depot: usize, customers: &[i64], rng: &mut R) -> Vec<Vec<i64>> {
+    let depot_node = problem.depot_node(depot);
+    let mut pairs = Vec::new();
+    for (k, &a) in customers.iter().enumerate() {
+        for &b in &customers[k + 1..] {
+            let (na, nb) = (Problem::customer_node(a), Problem::customer_node(b));
+            let saving = problem.distance(na, depot_node) + problem.distance(depot_node, nb) - problem.distance(na, nb);
+            let noise: f64 = rng.gen_range(-SAVINGS_NOISE, SAVINGS_NOISE);
+            pairs.push((saving*(1.0 + noise), a, b));
+        }
+    }
+    pairs.sort_by(|x, y| compare(y.0, x.0));
+
+    let mut routes: Vec<Vec<i64>> = customers.iter().map(|&c| vec![c]).collect();
+    let mut route_of = vec![0; problem.customers().len()];
+    for (r, &c) in customers.iter().enumerate() {
+        route_of[c as usize - 1] = r;
+    }
+    for &(saving, a, b) in &pairs {
+        if saving <= 0.0 {
+            break;
+        }
+        let (ra, rb) = (route_of[a as usize - 1], route_of[b as usize - 1]);
+        let at_end = |r: usize, c: i64| routes[r].first() == Some(&c) || routes[r].last() == Some(&c);
+        if ra == rb || !at_end(ra, a) || !at_end(rb, b) {
+            continue;
+        }
+        // The joined route ends its first part with a and starts its second part with b
+        let mut joined = routes[ra].clone();
+        if joined.last() != Some(&a) {
+            joined.reverse();
+        }
+        if routes[rb].first() == Some(&b) {
+            joined.extend_from_slice(&routes[rb]);
+        }
+        else {
+            joined.extend(routes[rb].iter().rev());
+        }
+        if evaluate_route(problem, depot, 0, &joined).violations.is_empty() {
+            for &c in &routes[rb] {
+                route_of[c as usize - 1] = ra;
+            }
+            routes[ra] = joined;
+            routes[rb].clear();
+        }
+    }
+    routes.into_iter().filter(|r| !r.is_empty()).collect()
+}
+
+// Customers are taken by angle around the depot from a random direction, a route ends when the next one doesn't fit
+fn sweep<R: Rng>(problem: &Problem, depot: usize, customers: &[i64], rng: &mut R) -> Vec<Vec<i64>> {
+    let d = &problem.depots()[depot];
+    let start: f64 = rng.gen_range(-PI, PI);
+    let angle = |c: i64| {
+        let cust = problem.customer(c);
+        ((cust.y() - d.y()).atan2(cust.x() - d.x()) - start).rem_euclid(2.0*PI)
+    };
+    let mut order = customers.to_vec();
+    order.sort_by(|&a, &b| compare(angle(a), angle(b)));
+
+    let mut routes: Vec<Vec<i64>> = Vec::new();
+    let mut route = RouteBuilder::new(problem, depot, 0);
+    let mut current = Vec::new();
+    for c in order {
+        let mut trial = route.clone();
+        trial.push(c);
+        if !current.is_empty() && !feasible(&trial) {
+            routes.push(current);
+            current = Vec::new();
+            trial = RouteBuilder::new(problem, depot, vehicle_of(problem, depot, routes.len()));
+            trial.push(c);
+        }
+        route = trial;
+        current.push(c);
+    }
+    if !current.is_empty() {
+        routes.push(current);
+    }
+    routes
+}
+
+// Each route starts at a random customer, then goes to the nearest customer that keeps it feasible
+fn nearest_neighbour<R: Rng>(problem: &Problem, depot: usize, customers: &[i64], rng: &mut R) -> Vec<Vec<i64>> {
+    let mut left = customers.to_vec();
+    let mut routes: Vec<Vec<i64>> = Vec::new();
+    while !left.is_empty() {
+        let mut route = RouteBuilder::new(problem, depot, vehicle_of(problem, depot, routes.len()));
+        let first = left.swap_remove(rng.gen_range(0, left.len()));
+        route.push(first);
+        let mut current = vec![first];
+        loop {
+            let node = Problem::customer_node(*current.last().unwrap());
+            let mut best: Option<(usize, RouteBuilder)> = None;
+            let mut best_dist = f64::INFINITY;
+            for (k, &c) in left.iter().enumerate() {
+                let dist = problem.distance(node, Problem::customer_node(c));
+                if dist >= best_dist {
+                    continue;
+                }
+                let mut trial = route.clone();
+                trial.push(c);
+                if feasible(&trial) {
+                    best = Some((k, trial));
+                    best_dist = dist;
+                }
+            }
+            match best {
+                None => break,
+                Some((k, trial)) => {
+                    current.push(left.swap_remove(k));
+                    route = trial;
+                },
+            }
+        }
+        routes.push(current);
+    }
+    routes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Encoding;
+    use crate::file_io;
+    use crate::random;
+
+    fn problem(name: &str) -> Problem {
+        let path = format!("{}/src/data/{}", env!("CARGO_MANIFEST_DIR"), name);
+        file_io::parse_cordeau(&file_io::read_to_string(&path).unwrap()).unwrap()
+    }
+
+    // Every customer exactly once, and as many zeros as the encoding has separators
+    fn assert_valid(chromosome: &[i64], problem: &Problem, config: &SolverConfig) {
+        let mut customers: Vec<i64> = chromosome.iter().copied().filter(|&c| c != 0).collect();
+        customers.sort_unstable();
+        assert_eq!(customers, (1..=problem.customers().len() as i64).collect::<Vec<i64>>());
+        assert_eq!(chromosome.len() - customers.len(), Genome::separators(problem, config));
+    }
+
+    // Sweep cuts more routes on p08 than its depots have vehicles, the extra ones must still fit in the fleet
+    #[test]
+    fn heuristics_build_permutations_that_fit_the_fleet() {
+        for name in ["p01", "p08"].iter() {
+            let problem = problem(name);
+            for encoding in [Encoding::Separators, Encoding::GiantTour].iter() {
+                let config = SolverConfig{encoding: *encoding, ..SolverConfig::default()};
+                for heuristic in [Heuristic::Savings, Heuristic::Sweep, Heuristic::NearestNeighbour].iter() {
+                    let mut valid = 0;
+                    for seed in 0..10 {
+                        let genome = construct(*heuristic, &problem, &config, &mut random::master_rng(seed));
+                        assert_valid(genome.chromosome(), &problem, &config);
+                        if genome.penalty() == 0.0 {valid = valid + 1;}
+                    }
+                    assert!(valid > 0);
+                }
+            }
+        }
+    }
+}
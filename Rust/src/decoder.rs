@@ -0,0 +1,179 @@
+use crate::solution::{Route, Violations};
+use crate::world::{Depot, Problem, VehicleType};
+
+// Route of a customer order, borrowing its customers from the order. Zeros separate the routes of
+// consecutive vehicles : the first vehicles belong to the first depot, then to the next one, and so on.
+pub struct RouteView<'a> {
+    pub depot: usize,
+    pub vehicle: usize,
+    pub customers: &'a [i64],
+    pub load: i64,
+    pub duration: f64,
+    pub waiting: f64,
+    pub distance: f64,
+    pub cost: f64,
+    pub violations: Violations,
+}
+
+impl<'a> RouteView<'a> {
+    pub fn to_route(&self) -> Route {
+        Route{
+            depot: self.depot,
+            vehicle: self.vehicle,
+            customers: self.customers.iter().map(|&c| c as usize).collect(),
+            load: self.load,
+            duration: self.duration,
+            waiting: self.waiting,
+            distance: self.distance,
+            cost: self.cost,
+            violations: self.violations,
+        }
+    }
+}
+
+pub struct RouteStats {
+    pub load: i64,
+    pub duration: f64,
+    pub waiting: f64,
+    pub distance: f64,
+    pub cost: f64,
+    pub violations: Violations,
+}
+
+// Drives the customers of a route from their depot and back, every constraint of the problem is checked here.
+// The vehicle leaves when the depot opens, and waits at customers it reaches before their time window.
+// Customers are added one at a time, so that every prefix of a sequence is evaluated in a single pass.
+#[derive(Clone)]
+pub struct RouteBuilder<'a> {
+    problem: &'a Problem,
+    depot: &'a Depot,
+    vehicle_type: &'a VehicleType,
+    depot_node: usize,
+    departure: f64,
+    node: usize,
+    empty: bool,
+    load: i64,
+    duration: f64,
+    waiting: f64,
+    lateness: f64,
+    distance: f64,
+}
+
+impl<'a> RouteBuilder<'a> {
+    pub fn new(problem: &'a Problem, depot: usize, vehicle: usize) -> RouteBuilder<'a> {
+        let depot_node = problem.depot_node(depot);
+        let depot = &problem.depots()[depot];
+        let vehicle_type = depot.vehicle_type(vehicle);
+        let departure = depot.opening().map_or(0.0, |w| w.earliest as f64);
+        RouteBuilder{problem, depot, vehicle_type, depot_node, departure, node: depot_node, empty: true,
+                     load: 0, duration: 0.0, waiting: 0.0, lateness: 0.0, distance: 0.0}
+    }
+
+    pub fn push(&mut self, c: i64) {
+        let cust = self.problem.customer(c);
+        let next = Problem::customer_node(c);
+        let dist = self.problem.distance(self.node, next);
+        let mut wait = 0.0;
+        if let Some(window) = cust.window() {
+            let arrival = self.departure + self.duration + dist;
+            if arrival < window.earliest as f64 {
+                wait = window.earliest as f64 - arrival;
+            }
+            else if arrival > window.latest as f64 {
+                self.lateness = self.lateness + arrival - window.latest as f64;
+            }
+        }
+        self.load = self.load + cust.load();
+        self.duration = self.duration + cust.duration() as f64 + dist + wait;
+        self.waiting = self.waiting + wait;
+        self.distance = self.distance + dist;
+        self.node = next;
+        self.empty = false;
+    }
+
+    // The route going back to its depot after the last customer
+    pub fn stats(&self) -> RouteStats {
+        let dist = self.problem.distance(self.node, self.depot_node);
+        let duration = self.duration + dist;
+        let distance = self.distance + dist;
+        let mut lateness = self.lateness;
+        if let Some(opening) = self.depot.opening() {
+            if self.departure + duration > opening.latest as f64 {
+                lateness = lateness + self.departure + duration - opening.latest as f64;
+            }
+        }
+
+        let vehicle_type = self.vehicle_type;
+        let mut violations = Violations{lateness, ..Violations::default()};
+        if vehicle_type.over_load(self.load) {
+            violations.load = self.load - vehicle_type.max_load();
+        }
+        if vehicle_type.over_duration(duration) {
+            violations.duration = duration - vehicle_type.max_duration() as f64;
+        }
+
+        let cost = vehicle_type.route_cost(distance, self.empty);
+        RouteStats{load: self.load, duration, waiting: self.waiting, distance, cost, violations}
+    }
+}
+
+pub fn evaluate_route(problem: &Problem, depot: usize, vehicle: usize, customers: &[i64]) -> RouteStats {
+    let mut route = RouteBuilder::new(problem, depot, vehicle);
+    for &c in customers {
+        route.push(c);
+    }
+    route.stats()
+}
+
+pub struct Decoder<'a> {
+    customer_order: &'a [i64],
+    problem: &'a Problem,
+    start: usize,
+    depot: usize,
+    vehicle: usize,
+    done: bool,
+}
+
+impl<'a> Decoder<'a> {
+    // Walks a customer order route by route, there is always one more route than there are zeros
+    pub fn new(customer_order: &'a [i64], problem: &'a Problem) -> Decoder<'a> {
+        Decoder{customer_order, problem, start: 0, depot: 0, vehicle: 0, done: false}
+    }
+}
+
+impl<'a> Iterator for Decoder<'a> {
+    type Item = RouteView<'a>;
+
+    fn next(&mut self) -> Option<RouteView<'a>> {
+        if self.done {
+            return None
+        }
+        let problem = self.problem;
+        let rest = &self.customer_order[self.start..];
+        let len = rest.iter().position(|&c| c == 0).unwrap_or(rest.len());
+        let customers = &rest[..len];
+
+        let stats = evaluate_route(problem, self.depot, self.vehicle, customers);
+        let depot = &problem.depots()[self.depot];
+
+        let route = RouteView{depot: self.depot, vehicle: self.vehicle, customers, load: stats.load, duration: stats.duration, waiting: stats.waiting, distance: stats.distance, cost: stats.cost, violations: stats.violations};
+
+        // Initialize new vehicle :
+        if len == rest.len() {
+            self.done = true;
+        }
+        else {
+            self.start = self.start + len + 1;
+            self.vehicle = self.vehicle + 1;
+            if self.vehicle >= depot.vehicles() as usize {
+                self.vehicle = 0;
+                self.depot = self.depot + 1;
+            }
+        }
+        Some(route)
+    }
+}
+
+pub fn decode(customer_order: &[i64], problem: &Problem) -> Vec<Route> {
+    Decoder::new(customer_order, problem).map(|r| r.to_route()).collect()
+}
@@ -1,332 +1,711 @@
-use std::collections::BTreeSet;
-
-use crate::genome::Genome;
-use crate::world::{Depot, Customer};
-
-use rand::prelude::*;
-use std::i64::MAX;
-
-const PROB_MUTATION: f64 = 0.2;
-const FRAC_INSERT: f64 = 0.4;
-const FRAC_SWAP: f64 = 0.3;
-const FRAC_SCRAMBLE: f64 = 0.3;
-
-const PROB_CROSSOVER: f64 = 0.9;
-const FRAC_ORDER1: f64 = 0.1;
-const FRAC_PMX: f64 = 0.9;
-const FRAC_EDGE_RECOMB: f64 = 0.0;
-
-pub fn mutate(mut old: Genome, depots: &Vec<Depot>, customers: &Vec<Customer>) -> Genome {
-    let mut rng = thread_rng();
-    let l = old.customer_order.len();
-    let mutat: f64 = rng.gen();
-
-    if mutat < PROB_MUTATION {
-        let mutat: f64 = rng.gen();
-        if mutat < FRAC_INSERT {
-            let src: usize = rng.gen_range(0, l);
-            let dst: usize = rng.gen_range(0, l);
-            let elem = old.customer_order.remove(src);
-            old.customer_order.insert(dst, elem);
-        }
-        else if mutat < FRAC_INSERT + FRAC_SWAP {
-            let src: usize = rng.gen_range(0, l);
-            let dst: usize = rng.gen_range(0, l);
-            let tmp = old.customer_order[src];
-            old.customer_order[src] = old.customer_order[dst];
-            old.customer_order[dst] = tmp;
-        }
-        else if mutat < FRAC_INSERT + FRAC_SWAP + FRAC_SCRAMBLE {
-            let len: usize = rng.gen_range(0, l);
-            let begin: usize = rng.gen_range(0, l - len);
-            let mut sub: Vec<i64> = old.customer_order.iter().skip(begin).take(len).map(|&e| e).collect();
-            sub.shuffle(&mut rng);
-            for (i, &e) in sub.iter().enumerate() {
-                old.customer_order[i + begin] = e;
-            }
-        }
-    }
-    Genome::generate(old.customer_order, depots, customers)
-}
-
-pub fn crossover(parent1: &Genome, parent2: &Genome, depots: &Vec<Depot>, customers: &Vec<Customer>, total_vehicles: usize) -> (Genome, Genome) {
-    let mut rng = thread_rng();
-    let child1: Vec<i64>;
-    let child2: Vec<i64>;
-    let cross: f64 = rng.gen();
-    if cross < PROB_CROSSOVER {
-        let cross: f64 = rng.gen();
-        if cross < FRAC_ORDER1 {
-            child1 = order_1_crossover(parent1, parent2, total_vehicles, &mut rng);
-            child2 = order_1_crossover(parent2, parent1, total_vehicles, &mut rng);
-        }
-        else if cross < FRAC_ORDER1 + FRAC_PMX {
-            let children = partially_mapped_crossover(parent1, parent2, customers.len(), &mut rng);
-            child1 = children.0;
-            child2 = children.1;
-        }
-        else if cross < FRAC_ORDER1 + FRAC_PMX + FRAC_EDGE_RECOMB {
-            let children = edge_recombination_crossover(parent1, parent2, customers.len(), &mut rng);
-            child1 = children.0;
-            child2 = children.1;
-        }
-        else {
-            child1 = parent1.customer_order.clone();
-            child2 = parent2.customer_order.clone();
-        }
-    }
-    else {
-        child1 = parent1.customer_order.clone();
-        child2 = parent2.customer_order.clone();
-    }
-    
-    (Genome::generate(child1, depots, customers), Genome::generate(child2, depots, customers))
-}   
-
-pub fn order_1_crossover(parent1: &Genome, parent2: &Genome, total_vehicles: usize, rng: &mut ThreadRng) -> Vec<i64> {
-    let mut child = Vec::new();
-
-    let len = rng.gen_range(0, parent1.customer_order.len());
-    let begin = rng.gen_range(0, parent1.customer_order.len() - len);
-    let mut zero_count = 0;
-    
-    for &n in parent1.customer_order.iter().skip(begin).take(len) {
-        if n == 0 {
-            zero_count = zero_count + 1;
-        }
-        child.push(n);
-    }
-    for &n in parent2.customer_order.iter().skip(begin+len).chain(parent2.customer_order.iter().take(begin+len)) {
-        if n == 0 && zero_count < total_vehicles - 1 {
-            zero_count = zero_count + 1;
-            child.push(n);
-        }
-        else if !child.contains(&n) {
-            child.push(n);
-        }
-    }
-    child
-}
-
-pub fn partially_mapped_crossover(parent1: &Genome, parent2: &Genome, num_customers: usize, rng: &mut ThreadRng) -> (Vec<i64>, Vec<i64>) {
-    // Idea : Transform all zeroes into num_customers + 1 to num_customers + num_vehicles, do algo then turn them back to 0
-    let mut p1: Vec<i64> = Vec::new();
-    let mut p2: Vec<i64> = Vec::new();
-    let mut transform = num_customers as i64;
-    for &c in &parent1.customer_order {
-        if c == 0 {
-            transform = transform + 1;
-            p1.push(transform);
-        }
-        else {
-            p1.push(c);
-        }
-    }
-    transform = num_customers as i64;
-    for &c in &parent2.customer_order {
-        if c == 0 {
-            transform = transform + 1;
-            p2.push(transform);
-        }
-        else {
-            p2.push(c);
-        }
-    }
-
-    let l = p1.len();
-    let len = rng.gen_range(0, l);
-    let begin = rng.gen_range(0, l - len);
-
-    let mut child1 = vec![MAX; l];
-    for (i, &n) in p1.iter().skip(begin).take(len).enumerate() {
-        child1[i+begin] = n;
-    }
-    for &n in p2.iter().skip(begin).take(len) {
-        if !child1.contains(&n) {
-            let mut var = n;
-            let mut idx;
-            let mut over = false;
-            while !over {
-                idx = p2.iter().position(|&el| el == var).unwrap();
-                var = p1[idx];
-                idx = p2.iter().position(|&el| el == var).unwrap();
-                if !(begin <= idx && idx < begin + len) {
-                    over = true;
-                    child1[idx] = n;
-                }
-            }
-        }
-    }
-    let mut next_empty = 0;
-    for &n in &p2 {
-        if !child1.contains(&n) {
-            while child1[next_empty] != MAX {
-                next_empty = next_empty + 1;
-            }
-            child1[next_empty] = n;
-        }
-    }
-
-    let mut child2 = vec![MAX; l];
-    for (i, &n) in p2.iter().skip(begin).take(len).enumerate() {
-        child2[i+begin] = n;
-    }
-    for &n in p1.iter().skip(begin).take(len) {
-        if !child2.contains(&n) {
-            let mut var = n;
-            let mut idx;
-            let mut over = false;
-            while !over {
-                idx = p1.iter().position(|&el| el == var).unwrap();
-                var = p2[idx];
-                idx = p1.iter().position(|&el| el == var).unwrap();
-                if !(begin <= idx && idx < begin + len) {
-                    over = true;
-                    child2[idx] = n;
-                }
-            }
-        }
-    }
-    let mut next_empty = 0;
-    for &n in &p1 {
-        if !child2.contains(&n) {
-            while child2[next_empty] != MAX {
-                next_empty = next_empty + 1;
-            }
-            child2[next_empty] = n;
-        }
-    }
-
-    for i in 0..child1.len() {
-        if child1[i] > num_customers as i64 {
-            child1[i] = 0;
-        }
-    }
-    for i in 0..child2.len() {
-        if child2[i] > num_customers as i64 {
-            child2[i] = 0;
-        }
-    }
-    (child1, child2)
-}
-
-pub fn edge_recombination_crossover(parent1: &Genome, parent2: &Genome, num_customers: usize, rng: &mut ThreadRng) -> (Vec<i64>, Vec<i64>) {
-    // Idea : Transform all zeroes into num_customers + 1 to num_customers + num_vehicles, do algo then turn them back to 0
-    let mut p1: Vec<i64> = Vec::new();
-    let mut p2: Vec<i64> = Vec::new();
-    let mut transform = num_customers as i64;
-    for &c in &parent1.customer_order {
-        if c == 0 {
-            transform = transform + 1;
-            p1.push(transform);
-        }
-        else {
-            p1.push(c);
-        }
-    }
-    transform = num_customers as i64;
-    for &c in &parent2.customer_order {
-        if c == 0 {
-            transform = transform + 1;
-            p2.push(transform);
-        }
-        else {
-            p2.push(c);
-        }
-    }
-    let mut child1 = Vec::new();
-    let mut child2 = Vec::new();
-
-    let mut neighbor_list1: Vec<BTreeSet<i64>> = vec![BTreeSet::new(); p1.len()];
-    for (i, &n) in p1.iter().enumerate() {
-        let before = if i == 0 {p1.len() - 1} else {i - 1};
-        let after = if i == p1.len() - 1 {0} else {i + 1};
-        neighbor_list1[(n - 1) as usize].insert(p1[before]);
-        neighbor_list1[(n - 1) as usize].insert(p1[after]);
-    }
-    for (i, &n) in p2.iter().enumerate() {
-        let before = if i == 0 {p2.len() - 1} else {i - 1};
-        let after = if i == p2.len() - 1 {0} else {i + 1};
-        neighbor_list1[(n - 1) as usize].insert(p2[before]);
-        neighbor_list1[(n - 1) as usize].insert(p2[after]);
-    }
-    let mut neighbor_list2 = neighbor_list1.clone();
-
-    let mut head = p1[0];
-    let mut inserted = BTreeSet::new();
-    for i in 1..=p1.len() {
-        inserted.insert(i);
-    }
-    child1.push(head);
-    while child1.len() < p1.len() {
-        inserted.remove(&(head as usize));
-        for i in 0..neighbor_list1.len() {
-            neighbor_list1[i].remove(&head);
-        }
-        if neighbor_list1[(head - 1) as usize].is_empty() {
-            let rand = rng.gen_range(0, inserted.len());
-            head = *inserted.iter().nth(rand).unwrap() as i64;
-        }
-        else {
-            let mut min = p1.len();
-            let mut candidates = Vec::new();
-            for &node in &neighbor_list1[(head - 1) as usize] {
-                if neighbor_list1[(node - 1) as usize].len() < min {
-                    candidates = vec![node];
-                    min = neighbor_list1[(node - 1) as usize].len();
-                }
-                else if neighbor_list1[(node - 1) as usize].len() == min {
-                    candidates.push(node);
-                }
-            }
-            let new_head_idx: usize = if candidates.len() == 1 {0} else {rng.gen_range(0, candidates.len())};
-            head = candidates[new_head_idx];
-        }
-        child1.push(head);
-    }
-    
-    let mut head = p2[0];
-    let mut inserted = BTreeSet::new();
-    for i in 1..=p2.len() {
-        inserted.insert(i);
-    }
-    child2.push(head);
-    while child2.len() < p2.len() {
-        inserted.remove(&(head as usize));
-        for i in 0..neighbor_list2.len() {
-            neighbor_list2[i].remove(&head);
-        }
-        if neighbor_list2[(head - 1) as usize].is_empty() {
-            let rand = rng.gen_range(0, inserted.len());
-            head = *inserted.iter().nth(rand).unwrap() as i64;
-        }
-        else {
-            let mut min = p2.len();
-            let mut candidates = Vec::new();
-            for &node in &neighbor_list2[(head - 1) as usize] {
-                if neighbor_list2[(node - 1) as usize].len() < min {
-                    candidates = vec![node];
-                    min = neighbor_list2[(node - 1) as usize].len();
-                }
-                else if neighbor_list2[(node - 1) as usize].len() == min {
-                    candidates.push(node);
-                }
-            }
-            let new_head_idx: usize = if candidates.len() == 1 {0} else {rng.gen_range(0, candidates.len())};
-            head = candidates[new_head_idx];
-        }
-        child2.push(head);
-    }
-
-    for i in 0..child1.len() {
-        if child1[i] > num_customers as i64 {
-            child1[i] = 0;
-        }
-    }
-    for i in 0..child2.len() {
-        if child2[i] > num_customers as i64 {
-            child2[i] = 0;
-        }
-    }
-    (child1, child2)
-}
\ No newline at end of file
+use crate::config::SolverConfig;
+use crate::decoder::{evaluate_route, Decoder, RouteBuilder};
+use crate::genome::Genome;
+use crate::local_search::LocalSearch;
+use crate::operators::{gain, Credits, OperatorWeights, Operators, BCRC, EDGE_RECOMB, INSERT, INTER_DEPOT, ORDER1, PMX, SCRAMBLE, SWAP};
+use crate::world::Problem;
+
+use rand::prelude::*;
+
+// Two children of the parents by crossover, mutation and education, with the rewards of the operators that made them :
+// the fitness gain of each child over the fitter parent for the crossover, and over the child before it for the mutation
+pub fn breed<R: Rng>(parent1: &Genome, parent2: &Genome, operators: &Operators, local_search: &LocalSearch, problem: &Problem, config: &SolverConfig, rng: &mut R) -> (Genome, Genome, Credits) {
+    let mut credits = Credits::default();
+    let (child1, child2, crossover_op) = crossover(parent1, parent2, &operators.crossover, problem, config, rng);
+    if let Some(k) = crossover_op {
+        let fitness = parent1.get_fitness().max(parent2.get_fitness());
+        credits.crossover.push((k, gain(fitness, child1.get_fitness())));
+        credits.crossover.push((k, gain(fitness, child2.get_fitness())));
+    }
+    let before = (child1.get_fitness(), child2.get_fitness());
+    let (child1, mutation1) = mutate(child1, operators, problem, config, rng);
+    let (child2, mutation2) = mutate(child2, operators, problem, config, rng);
+    if let Some(k) = mutation1 {
+        credits.mutation.push((k, gain(before.0, child1.get_fitness())));
+    }
+    if let Some(k) = mutation2 {
+        credits.mutation.push((k, gain(before.1, child2.get_fitness())));
+    }
+    let child1 = local_search.educate(child1, problem, config, rng);
+    let child2 = local_search.educate(child2, problem, config, rng);
+    (child1, child2, credits)
+}
+
+// Mutates the genome with probability config.prob_mutation, and returns the operator it used
+pub fn mutate<R: Rng>(old: Genome, operators: &Operators, problem: &Problem, config: &SolverConfig, rng: &mut R) -> (Genome, Option<usize>) {
+    let mut chromosome = old.into_chromosome();
+    let l = chromosome.len();
+    let mutat: f64 = rng.gen();
+    let mut operator = None;
+
+    if mutat < config.prob_mutation {
+        operator = operators.mutation.pick(rng.gen());
+        match operator {
+            Some(INSERT) => {
+                let src: usize = rng.gen_range(0, l);
+                let dst: usize = rng.gen_range(0, l);
+                let elem = chromosome.remove(src);
+                chromosome.insert(dst, elem);
+            },
+            Some(SWAP) => {
+                let src: usize = rng.gen_range(0, l);
+                let dst: usize = rng.gen_range(0, l);
+                chromosome.swap(src, dst);
+            },
+            Some(SCRAMBLE) => {
+                let len: usize = rng.gen_range(0, l);
+                let begin: usize = rng.gen_range(0, l - len);
+                let mut sub: Vec<i64> = chromosome.iter().skip(begin).take(len).copied().collect();
+                sub.shuffle(rng);
+                for (i, &e) in sub.iter().enumerate() {
+                    chromosome[i + begin] = e;
+                }
+            },
+            Some(INTER_DEPOT) => {
+                let genome = Genome::generate(chromosome, problem, config);
+                return (inter_depot(genome, &operators.borderline, problem, config, rng), operator)
+            },
+            _ => {},
+        }
+    }
+    (Genome::generate(chromosome, problem, config), operator)
+}
+
+// Moves a borderline customer, one with several close depots, to the cheapest place in a route of another of them
+// that keeps the route feasible. The genome is left as it is when there is none.
+fn inter_depot<R: Rng>(genome: Genome, borderline: &[(i64, Vec<usize>)], problem: &Problem, config: &SolverConfig, rng: &mut R) -> Genome {
+    if borderline.is_empty() {
+        return genome
+    }
+    let (c, depots) = &borderline[rng.gen_range(0, borderline.len())];
+
+    let mut slots = slots(&genome, problem, config);
+    let current = slots.iter().position(|s| s.customers.contains(c)).unwrap();
+    let from = slots[current].depot;
+    match cheapest_insertion(&slots, *c, |s| s.depot != from && depots.contains(&s.depot), problem, config) {
+        Some((r, at, true)) => {
+            slots[current].customers.retain(|x| x != c);
+            slots[r].customers.insert(at, *c);
+            from_slots(&slots, problem, config)
+        },
+        _ => genome,
+    }
+}
+
+// Route of a genome rebuilt by insertions
+struct Slot {
+    depot: usize,
+    vehicle: usize,
+    customers: Vec<i64>,
+    distance: f64,
+    cost: f64,
+    score: f64,
+}
+
+impl Slot {
+    fn update(&mut self, problem: &Problem, config: &SolverConfig) {
+        let stats = evaluate_route(problem, self.depot, self.vehicle, &self.customers);
+        self.distance = stats.distance;
+        self.cost = stats.cost;
+        self.score = stats.cost + Genome::weighted_penalty(&stats.violations, config);
+    }
+}
+
+fn slots(genome: &Genome, problem: &Problem, config: &SolverConfig) -> Vec<Slot> {
+    Decoder::new(&genome.customer_order, problem).map(|r| {
+        let mut slot = Slot{depot: r.depot, vehicle: r.vehicle, customers: r.customers.to_vec(), distance: 0.0, cost: 0.0, score: 0.0};
+        slot.update(problem, config);
+        slot
+    }).collect()
+}
+
+fn from_slots(slots: &[Slot], problem: &Problem, config: &SolverConfig) -> Genome {
+    let routes: Vec<Vec<i64>> = slots.iter().map(|s| s.customers.clone()).collect();
+    Genome::from_routes(routes.join(&0), problem, config)
+}
+
+// Cheapest place for customer c among the routes accepted, as (route, index, whether the route stays feasible).
+// Places that keep their route feasible come first, the others are scored with the penalty of their route.
+// Inserting a customer never lowers the penalty of a route, so the cost of the distance it adds is enough to drop most places.
+fn cheapest_insertion(slots: &[Slot], c: i64, accept: impl Fn(&Slot) -> bool, problem: &Problem, config: &SolverConfig) -> Option<(usize, usize, bool)> {
+    let node = Problem::customer_node(c);
+    let mut best: Option<(bool, f64, usize, usize)> = None;
+    for (r, slot) in slots.iter().enumerate() {
+        if !accept(slot) {
+            continue;
+        }
+        let depot_node = problem.depot_node(slot.depot);
+        let vehicle_type = problem.depots()[slot.depot].vehicle_type(slot.vehicle);
+        let mut prefix = RouteBuilder::new(problem, slot.depot, slot.vehicle);
+        for at in 0..=slot.customers.len() {
+            let before = if at == 0 {depot_node} else {Problem::customer_node(slot.customers[at - 1])};
+            let after = if at == slot.customers.len() {depot_node} else {Problem::customer_node(slot.customers[at])};
+            let added = problem.distance(before, node) + problem.distance(node, after) - problem.distance(before, after);
+            let bound = vehicle_type.route_cost(slot.distance + added, false) - slot.cost;
+            let hopeless = match best {
+                None => false,
+                Some(b) => b.0 && bound >= b.1,
+            };
+            if !hopeless {
+                let mut route = prefix.clone();
+                route.push(c);
+                for &d in &slot.customers[at..] {
+                    route.push(d);
+                }
+                let stats = route.stats();
+                let score = stats.cost + Genome::weighted_penalty(&stats.violations, config);
+                let place = (stats.violations.is_empty(), score - slot.score, r, at);
+                let better = match best {
+                    None => true,
+                    Some(b) => (place.0 && !b.0) || (place.0 == b.0 && place.1 < b.1),
+                };
+                if better {
+                    best = Some(place);
+                }
+            }
+            if at < slot.customers.len() {
+                prefix.push(slot.customers[at]);
+            }
+        }
+    }
+    best.map(|(feasible, _, r, at)| (r, at, feasible))
+}
+
+// Crosses the parents with probability config.prob_crossover, and returns the operator it used
+pub fn crossover<R: Rng>(parent1: &Genome, parent2: &Genome, weights: &OperatorWeights, problem: &Problem, config: &SolverConfig, rng: &mut R) -> (Genome, Genome, Option<usize>) {
+    let separators = Genome::separators(problem, config);
+    let num_customers = problem.customers().len();
+    let child1: Vec<i64>;
+    let child2: Vec<i64>;
+    let mut operator = None;
+    let cross: f64 = rng.gen();
+    if cross < config.prob_crossover {
+        operator = weights.pick(rng.gen());
+        match operator {
+            Some(ORDER1) => {
+                child1 = order_1_crossover(parent1, parent2, separators, rng);
+                child2 = order_1_crossover(parent2, parent1, separators, rng);
+            },
+            Some(PMX) => {
+                let children = partially_mapped_crossover(parent1, parent2, num_customers, rng);
+                child1 = children.0;
+                child2 = children.1;
+            },
+            Some(EDGE_RECOMB) => {
+                let children = edge_recombination_crossover(parent1, parent2, num_customers, rng);
+                child1 = children.0;
+                child2 = children.1;
+            },
+            Some(BCRC) => {
+                let (child1, child2) = best_cost_route_crossover(parent1, parent2, problem, config, rng);
+                return (child1, child2, operator)
+            },
+            _ => {
+                child1 = parent1.chromosome().clone();
+                child2 = parent2.chromosome().clone();
+            },
+        }
+    }
+    else {
+        child1 = parent1.chromosome().clone();
+        child2 = parent2.chromosome().clone();
+    }
+
+    (Genome::generate(child1, problem, config), Genome::generate(child2, problem, config), operator)
+}   
+
+// Best cost route crossover : each child is a parent without the customers of a random route of the other parent,
+// which are then inserted one at a time at their cheapest place in any route
+pub fn best_cost_route_crossover<R: Rng>(parent1: &Genome, parent2: &Genome, problem: &Problem, config: &SolverConfig, rng: &mut R) -> (Genome, Genome) {
+    let slots1 = slots(parent1, problem, config);
+    let slots2 = slots(parent2, problem, config);
+    let route1 = random_route(&slots1, rng);
+    let route2 = random_route(&slots2, rng);
+    (reinsert(slots1, &route2, problem, config), reinsert(slots2, &route1, problem, config))
+}
+
+// Customers of a random route that serves any, in a random order
+fn random_route<R: Rng>(slots: &[Slot], rng: &mut R) -> Vec<i64> {
+    let used: Vec<&Slot> = slots.iter().filter(|s| !s.customers.is_empty()).collect();
+    if used.is_empty() {
+        return Vec::new()
+    }
+    let mut customers = used[rng.gen_range(0, used.len())].customers.clone();
+    customers.shuffle(rng);
+    customers
+}
+
+fn reinsert(mut slots: Vec<Slot>, customers: &[i64], problem: &Problem, config: &SolverConfig) -> Genome {
+    for slot in slots.iter_mut() {
+        if slot.customers.iter().any(|c| customers.contains(c)) {
+            slot.customers.retain(|c| !customers.contains(c));
+            slot.update(problem, config);
+        }
+    }
+    for &c in customers {
+        let (r, at, _) = cheapest_insertion(&slots, c, |_| true, problem, config).unwrap();
+        slots[r].customers.insert(at, c);
+        slots[r].update(problem, config);
+    }
+    from_slots(&slots, problem, config)
+}
+
+// Order-1 : the child keeps a random slice of parent1, and takes the other customers in the order of parent2
+// from the end of the slice, with zeros until it has as many separators as the parents
+pub fn order_1_crossover<R: Rng>(parent1: &Genome, parent2: &Genome, separators: usize, rng: &mut R) -> Vec<i64> {
+    let p1 = parent1.chromosome();
+    let p2 = parent2.chromosome();
+    let l = p1.len();
+    let mut child = Vec::with_capacity(l);
+    let mut in_child = vec![false; l + 1];
+
+    let len = rng.gen_range(0, l);
+    let begin = rng.gen_range(0, l - len);
+    let mut zero_count = 0;
+
+    for &n in &p1[begin..begin + len] {
+        if n == 0 {
+            zero_count = zero_count + 1;
+        }
+        else {
+            in_child[n as usize] = true;
+        }
+        child.push(n);
+    }
+    for &n in p2[begin + len..].iter().chain(&p2[..begin + len]) {
+        if n == 0 {
+            if zero_count < separators {
+                zero_count = zero_count + 1;
+                child.push(n);
+            }
+        }
+        else if !in_child[n as usize] {
+            in_child[n as usize] = true;
+            child.push(n);
+        }
+    }
+    child
+}
+
+// Partially mapped crossover, with zeros relabelled as nodes of their own. Each child keeps a random slice of
+// one parent, the nodes of the other parent's slice that it lacks go where the mapping between the slices
+// sends them, and the rest of the other parent fills the remaining places.
+pub fn partially_mapped_crossover<R: Rng>(parent1: &Genome, parent2: &Genome, num_customers: usize, rng: &mut R) -> (Vec<i64>, Vec<i64>) {
+    let p1 = relabel(parent1.chromosome(), num_customers);
+    let p2 = relabel(parent2.chromosome(), num_customers);
+
+    let l = p1.len();
+    let len = rng.gen_range(0, l);
+    let begin = rng.gen_range(0, l - len);
+
+    let child1 = map_slice(&p1, &p2, begin, len);
+    let child2 = map_slice(&p2, &p1, begin, len);
+    (restore(child1, num_customers), restore(child2, num_customers))
+}
+
+// The mapping chains of different nodes never cross, so placing every node takes linear time overall
+fn map_slice(kept: &[i64], other: &[i64], begin: usize, len: usize) -> Vec<i64> {
+    let l = kept.len();
+    let mut position = vec![0; l + 1];
+    for (i, &n) in other.iter().enumerate() {
+        position[n as usize] = i;
+    }
+    let mut child = vec![0; l];
+    let mut in_child = vec![false; l + 1];
+    for i in begin..begin + len {
+        child[i] = kept[i];
+        in_child[kept[i] as usize] = true;
+    }
+    for &n in &other[begin..begin + len] {
+        if !in_child[n as usize] {
+            let mut idx = position[kept[position[n as usize]] as usize];
+            while begin <= idx && idx < begin + len {
+                idx = position[kept[idx] as usize];
+            }
+            child[idx] = n;
+            in_child[n as usize] = true;
+        }
+    }
+    let mut next_empty = 0;
+    for &n in other {
+        if !in_child[n as usize] {
+            while child[next_empty] != 0 {
+                next_empty = next_empty + 1;
+            }
+            child[next_empty] = n;
+        }
+    }
+    child
+}
+
+// Edge recombination : each child is assembled from the edges of both parents, seen as cycles. Zeros are relabelled
+// as nodes num_customers + 1 onwards so that every separator is a node of its own. From the first node of a parent,
+// the next node is the neighbour with the fewest neighbours left, or a random node when it has none.
+pub fn edge_recombination_crossover<R: Rng>(parent1: &Genome, parent2: &Genome, num_customers: usize, rng: &mut R) -> (Vec<i64>, Vec<i64>) {
+    let p1 = relabel(parent1.chromosome(), num_customers);
+    let p2 = relabel(parent2.chromosome(), num_customers);
+    let l = p1.len();
+    let mut neighbours: Vec<Vec<i64>> = vec![Vec::with_capacity(4); l];
+    for p in [&p1, &p2].iter() {
+        for (i, &n) in p.iter().enumerate() {
+            for &m in [p[(i + l - 1) % l], p[(i + 1) % l]].iter() {
+                if m != n && !neighbours[n as usize - 1].contains(&m) {
+                    neighbours[n as usize - 1].push(m);
+                }
+            }
+        }
+    }
+
+    let child1 = assemble_edges(p1[0], neighbours.clone(), rng);
+    let child2 = assemble_edges(p2[0], neighbours, rng);
+    (restore(child1, num_customers), restore(child2, num_customers))
+}
+
+// Walks the edge table from head until every node is in the child. A node only loses its neighbours as they
+// join the child, and has at most four, so each step takes constant time.
+fn assemble_edges<R: Rng>(mut head: i64, mut neighbours: Vec<Vec<i64>>, rng: &mut R) -> Vec<i64> {
+    let l = neighbours.len();
+    let mut child = Vec::with_capacity(l);
+    // Nodes not in the child yet, and the index of every node in that list
+    let mut left: Vec<i64> = (1..=l as i64).collect();
+    let mut index: Vec<usize> = (0..l).collect();
+    loop {
+        child.push(head);
+        let i = index[head as usize - 1];
+        left.swap_remove(i);
+        if i < left.len() {
+            index[left[i] as usize - 1] = i;
+        }
+        if left.is_empty() {
+            break;
+        }
+
+        let adjacent = std::mem::take(&mut neighbours[head as usize - 1]);
+        for &m in &adjacent {
+            neighbours[m as usize - 1].retain(|&x| x != head);
+        }
+        if adjacent.is_empty() {
+            head = left[rng.gen_range(0, left.len())];
+        }
+        else {
+            let min = adjacent.iter().map(|&m| neighbours[m as usize - 1].len()).min().unwrap();
+            let candidates: Vec<i64> = adjacent.into_iter().filter(|&m| neighbours[m as usize - 1].len() == min).collect();
+            let new_head_idx: usize = if candidates.len() == 1 {0} else {rng.gen_range(0, candidates.len())};
+            head = candidates[new_head_idx];
+        }
+    }
+    child
+}
+
+// Turns every zero into its own node after the customers, in order
+fn relabel(chromosome: &[i64], num_customers: usize) -> Vec<i64> {
+    let mut transform = num_customers as i64;
+    chromosome.iter().map(|&c| {
+        if c == 0 {
+            transform = transform + 1;
+            return transform
+        }
+        c
+    }).collect()
+}
+
+fn restore(mut child: Vec<i64>, num_customers: usize) -> Vec<i64> {
+    for c in child.iter_mut() {
+        if *c > num_customers as i64 {
+            *c = 0;
+        }
+    }
+    child
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assignment::borderline_customers;
+    use crate::config::Encoding;
+    use crate::file_io;
+    use crate::random;
+
+    fn problem() -> Problem {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/data/p01");
+        file_io::parse_cordeau(&file_io::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn configs() -> Vec<SolverConfig> {
+        let giant_tour = SolverConfig{encoding: Encoding::GiantTour, ..SolverConfig::default()};
+        vec![SolverConfig::default(), giant_tour]
+    }
+
+    // Every customer exactly once, and as many zeros as the encoding has separators
+    fn assert_valid(child: &[i64], problem: &Problem, config: &SolverConfig) {
+        let mut customers: Vec<i64> = child.iter().copied().filter(|&c| c != 0).collect();
+        customers.sort_unstable();
+        assert_eq!(customers, (1..=problem.customers().len() as i64).collect::<Vec<i64>>());
+        assert_eq!(child.len() - customers.len(), Genome::separators(problem, config));
+    }
+
+    #[test]
+    fn edge_recombination_children_are_permutations() {
+        let problem = problem();
+        for config in configs() {
+            for seed in 0..50 {
+                let mut rng = random::master_rng(seed);
+                let parent1 = Genome::random(&problem, &config, &mut rng);
+                let parent2 = Genome::random(&problem, &config, &mut rng);
+                let (child1, child2) = edge_recombination_crossover(&parent1, &parent2, problem.customers().len(), &mut rng);
+                assert_valid(&child1, &problem, &config);
+                assert_valid(&child2, &problem, &config);
+            }
+        }
+    }
+
+    #[test]
+    fn edge_recombination_of_a_genome_with_itself_keeps_its_cycle() {
+        let problem = problem();
+        let config = SolverConfig::default();
+        for seed in 0..20 {
+            let mut rng = random::master_rng(seed);
+            let parent = Genome::random(&problem, &config, &mut rng);
+            let (child, _) = edge_recombination_crossover(&parent, &parent, problem.customers().len(), &mut rng);
+            let order = parent.chromosome();
+            let mut reversed = vec![order[0]];
+            reversed.extend(order[1..].iter().rev());
+            assert!(&child == order || child == reversed);
+        }
+    }
+
+    // The quadratic implementations that order-1 and PMX replaced, which must draw and return the same children
+    fn reference_order_1<R: Rng>(parent1: &Genome, parent2: &Genome, separators: usize, rng: &mut R) -> Vec<i64> {
+        let mut child = Vec::new();
+
+        let len = rng.gen_range(0, parent1.chromosome().len());
+        let begin = rng.gen_range(0, parent1.chromosome().len() - len);
+        let mut zero_count = 0;
+
+        for &n in parent1.chromosome().iter().skip(begin).take(len) {
+            if n == 0 {
+                zero_count = zero_count + 1;
+            }
+            child.push(n);
+        }
+        for &n in parent2.chromosome().iter().skip(begin+len).chain(parent2.chromosome().iter().take(begin+len)) {
+            if n == 0 && zero_count < separators {
+                zero_count = zero_count + 1;
+                child.push(n);
+            }
+            else if !child.contains(&n) {
+                child.push(n);
+            }
+        }
+        child
+    }
+
+    fn reference_pmx<R: Rng>(parent1: &Genome, parent2: &Genome, num_customers: usize, rng: &mut R) -> (Vec<i64>, Vec<i64>) {
+        // Idea : Transform all zeroes into num_customers + 1 to num_customers + num_vehicles, do algo then turn them back to 0
+        let mut p1: Vec<i64> = Vec::new();
+        let mut p2: Vec<i64> = Vec::new();
+        let mut transform = num_customers as i64;
+        for &c in parent1.chromosome() {
+            if c == 0 {
+                transform = transform + 1;
+                p1.push(transform);
+            }
+            else {
+                p1.push(c);
+            }
+        }
+        transform = num_customers as i64;
+        for &c in parent2.chromosome() {
+            if c == 0 {
+                transform = transform + 1;
+                p2.push(transform);
+            }
+            else {
+                p2.push(c);
+            }
+        }
+
+        let l = p1.len();
+        let len = rng.gen_range(0, l);
+        let begin = rng.gen_range(0, l - len);
+
+        let mut child1 = vec![i64::MAX; l];
+        for (i, &n) in p1.iter().skip(begin).take(len).enumerate() {
+            child1[i+begin] = n;
+        }
+        for &n in p2.iter().skip(begin).take(len) {
+            if !child1.contains(&n) {
+                let mut var = n;
+                let mut idx;
+                let mut over = false;
+                while !over {
+                    idx = p2.iter().position(|&el| el == var).unwrap();
+                    var = p1[idx];
+                    idx = p2.iter().position(|&el| el == var).unwrap();
+                    if !(begin <= idx && idx < begin + len) {
+                        over = true;
+                        child1[idx] = n;
+                    }
+                }
+            }
+        }
+        let mut next_empty = 0;
+        for &n in &p2 {
+            if !child1.contains(&n) {
+                while child1[next_empty] != i64::MAX {
+                    next_empty = next_empty + 1;
+                }
+                child1[next_empty] = n;
+            }
+        }
+
+        let mut child2 = vec![i64::MAX; l];
+        for (i, &n) in p2.iter().skip(begin).take(len).enumerate() {
+            child2[i+begin] = n;
+        }
+        for &n in p1.iter().skip(begin).take(len) {
+            if !child2.contains(&n) {
+                let mut var = n;
+                let mut idx;
+                let mut over = false;
+                while !over {
+                    idx = p1.iter().position(|&el| el == var).unwrap();
+                    var = p2[idx];
+                    idx = p1.iter().position(|&el| el == var).unwrap();
+                    if !(begin <= idx && idx < begin + len) {
+                        over = true;
+                        child2[idx] = n;
+                    }
+                }
+            }
+        }
+        let mut next_empty = 0;
+        for &n in &p1 {
+            if !child2.contains(&n) {
+                while child2[next_empty] != i64::MAX {
+                    next_empty = next_empty + 1;
+                }
+                child2[next_empty] = n;
+            }
+        }
+
+        for c in child1.iter_mut().chain(child2.iter_mut()) {
+            if *c > num_customers as i64 {
+                *c = 0;
+            }
+        }
+        (child1, child2)
+    }
+
+    #[test]
+    fn order_1_matches_the_reference() {
+        let problem = problem();
+        for config in configs() {
+            let separators = Genome::separators(&problem, &config);
+            for seed in 0..200 {
+                let mut rng = random::master_rng(seed);
+                let parent1 = Genome::random(&problem, &config, &mut rng);
+                let parent2 = Genome::random(&problem, &config, &mut rng);
+                let child = order_1_crossover(&parent1, &parent2, separators, &mut random::master_rng(seed));
+                let reference = reference_order_1(&parent1, &parent2, separators, &mut random::master_rng(seed));
+                assert_valid(&child, &problem, &config);
+                assert_eq!(child, reference);
+            }
+        }
+    }
+
+    #[test]
+    fn pmx_matches_the_reference() {
+        let problem = problem();
+        let n = problem.customers().len();
+        for config in configs() {
+            for seed in 0..200 {
+                let mut rng = random::master_rng(seed);
+                let parent1 = Genome::random(&problem, &config, &mut rng);
+                let parent2 = Genome::random(&problem, &config, &mut rng);
+                let children = partially_mapped_crossover(&parent1, &parent2, n, &mut random::master_rng(seed));
+                let reference = reference_pmx(&parent1, &parent2, n, &mut random::master_rng(seed));
+                assert_valid(&children.0, &problem, &config);
+                assert_valid(&children.1, &problem, &config);
+                assert_eq!(children, reference);
+            }
+        }
+    }
+
+    #[test]
+    fn best_cost_route_children_are_permutations() {
+        let problem = problem();
+        for config in configs() {
+            for seed in 0..50 {
+                let mut rng = random::master_rng(seed);
+                let parent1 = Genome::random(&problem, &config, &mut rng);
+                let parent2 = Genome::random(&problem, &config, &mut rng);
+                let (child1, child2) = best_cost_route_crossover(&parent1, &parent2, &problem, &config, &mut rng);
+                assert_valid(child1.chromosome(), &problem, &config);
+                assert_valid(child2.chromosome(), &problem, &config);
+            }
+        }
+    }
+
+    // Every place of every route tried in turn : the added score of the cheapest one, preferring those that keep
+    // their route feasible, and whether it does
+    fn reference_cheapest_insertion(slots: &[Slot], c: i64, problem: &Problem, config: &SolverConfig) -> (bool, f64) {
+        let mut best = (false, f64::INFINITY);
+        for slot in slots {
+            for at in 0..=slot.customers.len() {
+                let mut customers = slot.customers.clone();
+                customers.insert(at, c);
+                let stats = evaluate_route(problem, slot.depot, slot.vehicle, &customers);
+                let place = (stats.violations.is_empty(), stats.cost + Genome::weighted_penalty(&stats.violations, config) - slot.score);
+                if (place.0 && !best.0) || (place.0 == best.0 && place.1 < best.1) {
+                    best = place;
+                }
+            }
+        }
+        best
+    }
+
+    // Unrepaired routes of the separators encoding, so that the child keeps the routes reinsert made
+    #[test]
+    fn best_cost_route_reinserts_at_the_cheapest_place() {
+        let problem = problem();
+        let config = SolverConfig{repaired: false, ..SolverConfig::default()};
+        let score = |slots: &[Slot]| slots.iter().map(|s| s.score).sum::<f64>();
+        for seed in 0..50 {
+            let mut rng = random::master_rng(seed);
+            let parent = Genome::random(&problem, &config, &mut rng);
+            let c = rng.gen_range(1, problem.customers().len() as i64 + 1);
+            let mut removed = slots(&parent, &problem, &config);
+            for slot in removed.iter_mut().filter(|s| s.customers.contains(&c)) {
+                slot.customers.retain(|&d| d != c);
+                slot.update(&problem, &config);
+            }
+            let (feasible, added) = reference_cheapest_insertion(&removed, c, &problem, &config);
+
+            let child = reinsert(slots(&parent, &problem, &config), &[c], &problem, &config);
+            assert_valid(child.chromosome(), &problem, &config);
+            let child_slots = slots(&child, &problem, &config);
+            let route = child_slots.iter().find(|s| s.customers.contains(&c)).unwrap();
+            assert_eq!(evaluate_route(&problem, route.depot, route.vehicle, &route.customers).violations.is_empty(), feasible);
+            assert!((score(&child_slots) - score(&removed) - added).abs() < 1e-6);
+        }
+    }
+
+    // The moved customer only goes to a place that keeps its new route feasible, so no route gets a worse penalty
+    #[test]
+    fn inter_depot_never_adds_penalty() {
+        let problem = problem();
+        for config in configs() {
+            let borderline = borderline_customers(&problem, config.borderline_margin);
+            assert!(!borderline.is_empty());
+            for seed in 0..50 {
+                let mut rng = random::master_rng(seed);
+                let genome = Genome::random(&problem, &config, &mut rng);
+                let penalty = genome.penalty();
+                let moved = inter_depot(genome, &borderline, &problem, &config, &mut rng);
+                assert_valid(moved.chromosome(), &problem, &config);
+                assert!(moved.penalty() <= penalty + 1e-9);
+            }
+        }
+    }
+}
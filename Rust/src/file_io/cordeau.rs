@@ -0,0 +1,176 @@
+use super::record::{records, ParseError, ParseErrorKind, Record};
+use crate::world::{Customer, Depot, Problem, TimeWindow};
+
+const TYPE_MDVRP: i64 = 2;
+const TYPE_MDVRPTW: i64 = 6;
+
+// Reads an instance in the Cordeau MDVRP or MDVRPTW format :
+// a "vehicles customers depots" header, or "type vehicles customers depots" with type 2 (MDVRP) or 6 (MDVRPTW),
+// one "max_duration max_load" line per depot,
+// one "number x y service_duration demand frequency combinations list... [earliest latest]" line per customer,
+// then one "number x y ... [earliest latest]" line per depot. Time windows are only read for type 6.
+// Blank lines and anything after a '#' are ignored.
+pub fn parse_cordeau(input: &str) -> Result<Problem, ParseError> {
+    let mut records = records(input);
+    let last_line = input.lines().count();
+
+    let mut header = match records.next() {
+        None => return Err(ParseError{line: last_line.max(1), column: 0, kind: ParseErrorKind::MissingLines{expected: String::from("a header line"), found: 0}}),
+        Some(r) => r,
+    };
+    let mut time_windows = false;
+    if header.fields.len() == 4 {
+        let (column, problem_type) = header.next::<i64>("problem type")?;
+        match problem_type {
+            TYPE_MDVRP => (),
+            TYPE_MDVRPTW => time_windows = true,
+            _ => return Err(header.error(column, ParseErrorKind::InvalidValue{expected: "problem type 2 (MDVRP) or 6 (MDVRPTW)", found: problem_type})),
+        }
+    }
+    let vehicles_per_depot = header.positive("number of vehicles per depot")?;
+    let n_customers = header.positive("number of customers")?;
+    let n_depots = header.positive("number of depots")?;
+    header.end()?;
+
+    let missing = |expected: String, found: usize| ParseError{line: last_line, column: 0, kind: ParseErrorKind::MissingLines{expected, found}};
+
+    let mut depot_limits = Vec::new();
+    for i in 0..n_depots as usize {
+        let mut limit = records.next().ok_or_else(|| missing(format!("{} depot limit lines", n_depots), i))?;
+        let max_duration = limit.non_negative("maximum route duration")?;
+        let max_load = limit.positive("maximum vehicle load")?;
+        limit.end()?;
+        depot_limits.push((max_duration, max_load));
+    }
+
+    let mut customers = Vec::new();
+    for i in 0..n_customers as usize {
+        let mut record = records.next().ok_or_else(|| missing(format!("{} customer lines", n_customers), i))?;
+        record.index("customer", i as i64 + 1)?;
+        let (_, x) = record.next::<f64>("x coordinate")?;
+        let (_, y) = record.next::<f64>("y coordinate")?;
+        let duration = record.non_negative("service duration")?;
+        let load = record.non_negative("demand")?;
+        let customer = Customer::init(x, y, duration, load);
+        customers.push(match time_window(&mut record, time_windows)? {
+            None => customer,
+            Some(window) => customer.with_window(window),
+        });
+    }
+
+    let mut depots = Vec::new();
+    for (i, &(max_duration, max_load)) in depot_limits.iter().enumerate() {
+        let mut record = records.next().ok_or_else(|| missing(format!("{} depot lines", n_depots), i))?;
+        record.index("depot", n_customers + i as i64 + 1)?;
+        let (_, x) = record.next::<f64>("x coordinate")?;
+        let (_, y) = record.next::<f64>("y coordinate")?;
+        let depot = Depot::init(x, y, max_duration, max_load, vehicles_per_depot);
+        if time_windows {
+            record.number("service duration")?;
+            record.number("demand")?;
+        }
+        depots.push(match time_window(&mut record, time_windows)? {
+            None => depot,
+            Some(window) => depot.with_opening(window),
+        });
+    }
+
+    if let Some(record) = records.next() {
+        return Err(record.error(0, ParseErrorKind::ExtraLines{expected: (2*n_depots + n_customers) as usize}))
+    }
+    Ok(Problem::new(depots, customers))
+}
+
+// Skips the visit frequency and combinations of a customer or depot line to read the time window at its end
+fn time_window(record: &mut Record, time_windows: bool) -> Result<Option<TimeWindow>, ParseError> {
+    if !time_windows {
+        return Ok(None)
+    }
+    record.non_negative("visit frequency")?;
+    let combinations = record.non_negative("number of visit combinations")?;
+    for _ in 0..combinations {
+        record.number("visit combination")?;
+    }
+    let earliest = record.number("start of the time window")?;
+    let (column, latest) = record.next::<i64>("end of the time window")?;
+    if latest < earliest {
+        return Err(record.error(column, ParseErrorKind::InvalidValue{expected: "an end of the time window after its start", found: latest}))
+    }
+    record.end()?;
+    Ok(Some(TimeWindow{earliest, latest}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: &str) -> ParseError {
+        parse_cordeau(input).err().unwrap()
+    }
+
+    #[test]
+    fn reads_crlf_tabs_and_comments() {
+        let input = "# two customers\r\n2 4 2 1\r\n0\t50 # limits\r\n\r\n1 10 10 5 20 1 1 1\r\n2\t20\t10\t5 30 1 1 1\r\n3 0 0   # depot\r\n";
+        let problem = parse_cordeau(input).unwrap();
+        assert_eq!(problem.customers().len(), 2);
+        assert_eq!(problem.depots().len(), 1);
+        assert_eq!(problem.customer(2).load(), 30);
+        assert_eq!(problem.customer(2).x(), 20.0);
+        assert_eq!(problem.depots()[0].vehicles(), 4);
+    }
+
+    #[test]
+    fn reads_time_windows_of_type_6() {
+        let input = "6 1 1 1\n0 50\n1 10 10 5 20 1 1 1 0 100\n2 0 0 0 0 0 0 0 500\n";
+        let problem = parse_cordeau(input).unwrap();
+        assert_eq!(problem.customer(1).window(), Some(TimeWindow{earliest: 0, latest: 100}));
+        assert_eq!(problem.depots()[0].opening(), Some(TimeWindow{earliest: 0, latest: 500}));
+    }
+
+    #[test]
+    fn reports_the_line_and_column_of_a_bad_number() {
+        let e = error("4 1 1\n0 5x\n");
+        assert_eq!((e.line, e.column), (2, 3));
+        assert_eq!(e.kind, ParseErrorKind::InvalidNumber{expected: "maximum vehicle load", found: String::from("5x")});
+
+        // A tab is one column and a carriage return is not part of the field
+        let e = error("4 1 1\r\n\t0 x\r\n");
+        assert_eq!((e.line, e.column), (2, 4));
+        assert_eq!(e.kind, ParseErrorKind::InvalidNumber{expected: "maximum vehicle load", found: String::from("x")});
+    }
+
+    #[test]
+    fn a_comment_ends_the_line() {
+        let e = error("4 1 1\n0 # 50\n");
+        assert_eq!((e.line, e.column), (2, 2));
+        assert_eq!(e.kind, ParseErrorKind::MissingField{expected: "maximum vehicle load"});
+    }
+
+    #[test]
+    fn reports_wrong_numbers_and_values() {
+        let e = error("4 1 1\n0 50\n\n2 10 10 5 20 1 1 1\n");
+        assert_eq!((e.line, e.column), (4, 1));
+        assert_eq!(e.kind, ParseErrorKind::WrongNumber{expected: "customer", number: 1, found: 2});
+
+        let e = error("3 4 1 1\n");
+        assert_eq!((e.line, e.column), (1, 1));
+
+        let e = error("6 1 1 1\n0 50\n1 10 10 5 20 1 1 1 100 0\n");
+        assert_eq!((e.line, e.column), (3, 24));
+        assert_eq!(e.kind, ParseErrorKind::InvalidValue{expected: "an end of the time window after its start", found: 0});
+    }
+
+    #[test]
+    fn reports_missing_and_extra_lines() {
+        let e = error("4 2 1\n0 50\n1 10 10 5 20\n");
+        assert_eq!((e.line, e.column), (3, 0));
+        assert_eq!(e.kind, ParseErrorKind::MissingLines{expected: String::from("2 customer lines"), found: 1});
+
+        let e = error("4 1 1\n0 50\n1 10 10 5 20\n2 0 0\n\n2 0 0\n");
+        assert_eq!((e.line, e.column), (6, 0));
+        assert_eq!(e.kind, ParseErrorKind::ExtraLines{expected: 3});
+
+        let e = error("# nothing\n");
+        assert_eq!((e.line, e.column), (1, 0));
+    }
+}
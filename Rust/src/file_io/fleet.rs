@@ -0,0 +1,24 @@
+use super::record::{records, ParseError, ParseErrorKind};
+use crate::world::VehicleType;
+
+// Reads the vehicle types of the depots, one "depot count max_duration max_load fixed_cost distance_cost" line per type.
+// Depots are numbered from 1 and a depot may have several types, its vehicles follow the order of the lines.
+// The result has one fleet per depot, left empty for depots that keep the vehicles of the instance.
+// Blank lines and anything after a '#' are ignored.
+pub fn parse_fleet(input: &str, n_depots: usize) -> Result<Vec<Vec<VehicleType>>, ParseError> {
+    let mut fleets = vec![Vec::new(); n_depots];
+    for mut record in records(input) {
+        let (column, depot) = record.next::<i64>("depot number")?;
+        if depot < 1 || depot as usize > n_depots {
+            return Err(record.error(column, ParseErrorKind::InvalidValue{expected: "a depot number of the instance", found: depot}))
+        }
+        let count = record.positive("number of vehicles")?;
+        let max_duration = record.non_negative("maximum route duration")?;
+        let max_load = record.positive("maximum vehicle load")?;
+        let fixed_cost = record.non_negative_real("non-negative fixed cost")?;
+        let distance_cost = record.non_negative_real("non-negative cost per distance")?;
+        record.end()?;
+        fleets[depot as usize - 1].push(VehicleType::init(max_duration, max_load, fixed_cost, distance_cost, count));
+    }
+    Ok(fleets)
+}
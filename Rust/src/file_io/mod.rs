@@ -1,35 +1,53 @@
-use std::error::Error;
-use std::fs;
-use std::fs::File;
-use std::io::prelude::*;
-use std::path::Path;
-
-pub fn read_to_string(path: &str) -> String {
-    return fs::read_to_string(path).expect("Something went wrong reading the file")
-}
-
-pub fn _read_to_string2(path: &str) -> String {
-    let path = Path::new(path);
-    let display = path.display();
-	
-	let mut s = String::new();
-    let mut file = match File::open(&path) {
-        Err(why) => panic!("couldn't open {}: {}", display,
-                                                   why.description()),
-        Ok(file) => file,
-    };
-    
-    match file.read_to_string(&mut s) {
-        Err(why) => panic!("couldn't read {}: {}", display,
-                                                   why.description()),
-        Ok(_) => (),
-    }
-    
-    return s
-}
-
-pub fn write_to_file(path: &str, text: &str) -> std::io::Result<()> {
-    let mut file = File::create(path)?;
-    file.write_all(text.as_bytes())?;
-    Ok(())
-}
\ No newline at end of file
+use std::fs;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+use crate::world::Problem;
+
+mod cordeau;
+mod fleet;
+mod record;
+mod result;
+mod solomon;
+
+pub use cordeau::parse_cordeau;
+pub use fleet::parse_fleet;
+pub use solomon::parse_solomon;
+pub use record::{ParseError, ParseErrorKind};
+pub use result::{parse_result, ResultFile, ResultRoute};
+
+// Solomon files start with the instance name, Cordeau files with their header numbers
+pub fn parse_instance(input: &str) -> Result<Problem, ParseError> {
+    match record::records(input).next() {
+        Some(r) if r.fields[0].1.parse::<f64>().is_err() => parse_solomon(input),
+        _ => parse_cordeau(input),
+    }
+}
+
+pub fn read_to_string<P: AsRef<Path>>(path: P) -> std::io::Result<String> {
+    fs::read_to_string(path)
+}
+
+pub fn _read_to_string2(path: &str) -> String {
+    let path = Path::new(path);
+    let display = path.display();
+	
+	let mut s = String::new();
+    let mut file = match File::open(path) {
+        Err(why) => panic!("couldn't open {}: {}", display, why),
+        Ok(file) => file,
+    };
+    
+    if let Err(why) = file.read_to_string(&mut s) {
+        panic!("couldn't read {}: {}", display, why)
+    }
+    
+    return s
+}
+
+pub fn write_to_file<P: AsRef<Path>>(path: P, text: &str) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(text.as_bytes())?;
+    Ok(())
+}
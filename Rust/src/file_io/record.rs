@@ -0,0 +1,152 @@
+use std::fmt;
+use std::str::FromStr;
+
+// Line and column are counted from 1, a column of 0 means the error concerns the whole line
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseErrorKind {
+    MissingField { expected: &'static str },
+    InvalidNumber { expected: &'static str, found: String },
+    InvalidValue { expected: &'static str, found: i64 },
+    UnexpectedField { found: String },
+    WrongNumber { expected: &'static str, number: i64, found: i64 },
+    MissingLines { expected: String, found: usize },
+    ExtraLines { expected: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.column == 0 {
+            write!(f, "line {}: ", self.line)?;
+        }
+        else {
+            write!(f, "line {}, column {}: ", self.line, self.column)?;
+        }
+        match &self.kind {
+            ParseErrorKind::MissingField { expected } => write!(f, "expected {}, found end of line", expected),
+            ParseErrorKind::InvalidNumber { expected, found } => write!(f, "expected {}, found '{}'", expected, found),
+            ParseErrorKind::InvalidValue { expected, found } => write!(f, "expected {}, found {}", expected, found),
+            ParseErrorKind::UnexpectedField { found } => write!(f, "expected end of line, found '{}'", found),
+            ParseErrorKind::WrongNumber { expected, number, found } => write!(f, "expected {} number {}, found {}", expected, number, found),
+            ParseErrorKind::MissingLines { expected, found } => write!(f, "file ends early, expected {} but found {}", expected, found),
+            ParseErrorKind::ExtraLines { expected } => write!(f, "unexpected data, the header declares {} lines after itself", expected),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// A non empty line, split into fields with their 1-based column
+pub(super) struct Record<'a> {
+    pub(super) line: usize,
+    pub(super) fields: Vec<(usize, &'a str)>,
+    next: usize,
+}
+
+impl<'a> Record<'a> {
+    pub(super) fn new(line: usize, text: &'a str) -> Record<'a> {
+        let mut fields = Vec::new();
+        let mut start = None;
+        for (i, c) in text.char_indices() {
+            match (c.is_whitespace(), start) {
+                (false, None) => start = Some(i),
+                (true, Some(s)) => {
+                    fields.push((s + 1, &text[s..i]));
+                    start = None;
+                },
+                _ => (),
+            }
+        }
+        if let Some(s) = start {
+            fields.push((s + 1, &text[s..]));
+        }
+        Record{line, fields, next: 0}
+    }
+
+    pub(super) fn error(&self, column: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError{line: self.line, column, kind}
+    }
+
+    pub(super) fn next<T: FromStr>(&mut self, expected: &'static str) -> Result<(usize, T), ParseError> {
+        match self.fields.get(self.next) {
+            None => {
+                let column = self.fields.last().map_or(1, |&(c, s)| c + s.len());
+                Err(self.error(column, ParseErrorKind::MissingField{expected}))
+            },
+            Some(&(column, s)) => {
+                self.next = self.next + 1;
+                match s.parse::<T>() {
+                    Ok(n) => Ok((column, n)),
+                    Err(_) => Err(self.error(column, ParseErrorKind::InvalidNumber{expected, found: s.to_string()})),
+                }
+            },
+        }
+    }
+
+    pub(super) fn number(&mut self, expected: &'static str) -> Result<i64, ParseError> {
+        self.next::<i64>(expected).map(|(_, n)| n)
+    }
+
+    pub(super) fn non_negative(&mut self, expected: &'static str) -> Result<i64, ParseError> {
+        let (column, n) = self.next::<i64>(expected)?;
+        if n < 0 {
+            return Err(self.error(column, ParseErrorKind::InvalidValue{expected, found: n}))
+        }
+        Ok(n)
+    }
+
+    pub(super) fn positive(&mut self, expected: &'static str) -> Result<i64, ParseError> {
+        let (column, n) = self.next::<i64>(expected)?;
+        if n <= 0 {
+            return Err(self.error(column, ParseErrorKind::InvalidValue{expected, found: n}))
+        }
+        Ok(n)
+    }
+
+    pub(super) fn non_negative_real(&mut self, expected: &'static str) -> Result<f64, ParseError> {
+        let (column, n) = self.next::<f64>(expected)?;
+        if n.is_nan() || n < 0.0 || n.is_infinite() {
+            return Err(self.error(column, ParseErrorKind::InvalidNumber{expected, found: n.to_string()}))
+        }
+        Ok(n)
+    }
+
+    // Checks the number at the start of customer and depot lines
+    pub(super) fn index(&mut self, expected: &'static str, number: i64) -> Result<(), ParseError> {
+        let (column, n) = self.next::<i64>(expected)?;
+        if n != number {
+            return Err(self.error(column, ParseErrorKind::WrongNumber{expected, number, found: n}))
+        }
+        Ok(())
+    }
+
+    // Fields that have not been read yet
+    pub(super) fn remaining(&self) -> &[(usize, &'a str)] {
+        &self.fields[self.next..]
+    }
+
+    pub(super) fn end(&self) -> Result<(), ParseError> {
+        match self.fields.get(self.next) {
+            None => Ok(()),
+            Some(&(column, s)) => Err(self.error(column, ParseErrorKind::UnexpectedField{found: s.to_string()})),
+        }
+    }
+}
+
+// Non empty lines of the input, with anything after a '#' removed
+pub(super) fn records(input: &str) -> impl Iterator<Item = Record<'_>> {
+    input.lines().enumerate().filter_map(|(i, l)| {
+        let content = match l.find('#') {
+            None => l,
+            Some(idx) => &l[..idx],
+        };
+        let record = Record::new(i + 1, content);
+        if record.fields.is_empty() {None} else {Some(record)}
+    })
+}
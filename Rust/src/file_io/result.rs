@@ -0,0 +1,65 @@
+use super::record::{records, ParseError, ParseErrorKind};
+
+// A route as written in a result file, depot and vehicle are indexes starting from 0
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResultRoute {
+    pub line: usize,
+    pub depot: usize,
+    pub vehicle: usize,
+    pub duration: f64,
+    pub load: i64,
+    pub customers: Vec<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResultFile {
+    pub cost: f64,
+    pub routes: Vec<ResultRoute>,
+}
+
+// Reads a solution written by Solution::result_text or in the Cordeau .res format :
+// the total cost, then one "depot vehicle duration load start customers... end" line per route.
+// The start and end of a route are 0 or the depot number, and customers may be followed by their
+// arrival time in parentheses. Empty routes may be omitted.
+pub fn parse_result(input: &str) -> Result<ResultFile, ParseError> {
+    let mut records = records(input);
+    let mut header = match records.next() {
+        None => return Err(ParseError{line: input.lines().count().max(1), column: 0, kind: ParseErrorKind::MissingLines{expected: String::from("the total cost"), found: 0}}),
+        Some(r) => r,
+    };
+    let (_, cost) = header.next::<f64>("total cost")?;
+    header.end()?;
+
+    let mut routes = Vec::new();
+    for mut record in records {
+        let depot = record.positive("depot number")? as usize - 1;
+        let vehicle = record.positive("vehicle number")? as usize - 1;
+        let (_, duration) = record.next::<f64>("route duration")?;
+        let load = record.non_negative("route load")?;
+
+        // Arrival times, as in "12(34.50)", are not needed to check the route
+        let mut nodes = Vec::new();
+        for &(column, field) in record.remaining() {
+            let number = match field.find('(') {
+                None => field,
+                Some(idx) => &field[..idx],
+            };
+            match number.parse::<i64>() {
+                Ok(n) if n >= 0 => nodes.push((column, n)),
+                _ => return Err(record.error(column, ParseErrorKind::InvalidNumber{expected: "customer number", found: field.to_string()})),
+            }
+        }
+        if nodes.len() < 2 {
+            let column = record.fields.last().map_or(1, |&(c, s)| c + s.len());
+            return Err(record.error(column, ParseErrorKind::MissingField{expected: "depot number at both ends of the route"}))
+        }
+        let (_, start) = nodes[0];
+        let (column, end) = nodes[nodes.len() - 1];
+        if end != start {
+            return Err(record.error(column, ParseErrorKind::InvalidValue{expected: "the depot number that starts the route", found: end}))
+        }
+        let customers = nodes[1..nodes.len() - 1].iter().map(|&(_, c)| c).collect();
+        routes.push(ResultRoute{line: record.line, depot, vehicle, duration, load, customers});
+    }
+    Ok(ResultFile{cost, routes})
+}
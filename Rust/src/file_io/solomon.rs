@@ -0,0 +1,81 @@
+use super::record::{records, ParseError, ParseErrorKind};
+use crate::world::{Customer, Depot, Problem, TimeWindow};
+
+// Reads a Solomon VRPTW instance as a problem with a single depot :
+// a name, the "VEHICLE" section with the number of vehicles and their capacity, then the "CUSTOMER" section
+// with one "number x y demand ready_time due_date service_time" line per node, the depot being number 0.
+// The opening hours of the depot are its ready time and due date, routes have no other duration limit.
+pub fn parse_solomon(input: &str) -> Result<Problem, ParseError> {
+    // Section titles and column headers are the only lines that don't start with a number
+    let mut records = records(input).filter(|r| r.fields[0].1.parse::<f64>().is_ok());
+    let last_line = input.lines().count().max(1);
+    let missing = |expected: &str, found: usize| ParseError{line: last_line, column: 0, kind: ParseErrorKind::MissingLines{expected: expected.to_string(), found}};
+
+    let mut fleet = records.next().ok_or_else(|| missing("the number of vehicles and their capacity", 0))?;
+    let vehicles = fleet.positive("number of vehicles")?;
+    let capacity = fleet.positive("vehicle capacity")?;
+    fleet.end()?;
+
+    let mut depot = None;
+    let mut customers = Vec::new();
+    for (i, mut record) in records.enumerate() {
+        record.index(if i == 0 {"depot"} else {"customer"}, i as i64)?;
+        let (_, x) = record.next::<f64>("x coordinate")?;
+        let (_, y) = record.next::<f64>("y coordinate")?;
+        let load = record.non_negative("demand")?;
+        let earliest = record.non_negative("ready time")?;
+        let (column, latest) = record.next::<i64>("due date")?;
+        if latest < earliest {
+            return Err(record.error(column, ParseErrorKind::InvalidValue{expected: "a due date after the ready time", found: latest}))
+        }
+        let duration = record.non_negative("service time")?;
+        record.end()?;
+
+        let window = TimeWindow{earliest, latest};
+        if i == 0 {
+            depot = Some(Depot::init(x, y, 0, capacity, vehicles).with_opening(window));
+        }
+        else {
+            customers.push(Customer::init(x, y, duration, load).with_window(window));
+        }
+    }
+
+    let depot = depot.ok_or_else(|| missing("the depot line", 0))?;
+    if customers.is_empty() {
+        return Err(missing("customer lines", 0))
+    }
+    Ok(Problem::new(vec![depot], customers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "C101\n\nVEHICLE\nNUMBER     CAPACITY\n  25         200\n\nCUSTOMER\nCUST NO.  XCOORD.   YCOORD.    DEMAND   READY TIME  DUE DATE   SERVICE   TIME\n\n";
+
+    #[test]
+    fn reads_crlf_tabs_and_comments() {
+        let input = HEADER.replace('\n', "\r\n") + "    0      40\t50          0          0       1236          0   # depot\r\n\t1      45         68         10        912        967         90\r\n";
+        let problem = parse_solomon(&input).unwrap();
+        assert_eq!(problem.customers().len(), 1);
+        assert_eq!(problem.depots()[0].vehicles(), 25);
+        assert_eq!(problem.depots()[0].opening(), Some(TimeWindow{earliest: 0, latest: 1236}));
+        assert_eq!(problem.customer(1).window(), Some(TimeWindow{earliest: 912, latest: 967}));
+        assert_eq!(problem.customer(1).duration(), 90);
+    }
+
+    #[test]
+    fn reports_the_line_and_column_of_an_error() {
+        let e = parse_solomon(&(HEADER.to_string() + "0 40 50 0 0 1236 0\n1 45 68 10 912 900 90\n")).err().unwrap();
+        assert_eq!((e.line, e.column), (11, 16));
+        assert_eq!(e.kind, ParseErrorKind::InvalidValue{expected: "a due date after the ready time", found: 900});
+
+        let e = parse_solomon(&(HEADER.to_string() + "0 40 50 0 0 1236 0\r\n2\t45 68 10 912 967 90\r\n")).err().unwrap();
+        assert_eq!((e.line, e.column), (11, 1));
+        assert_eq!(e.kind, ParseErrorKind::WrongNumber{expected: "customer", number: 1, found: 2});
+
+        let e = parse_solomon(&(HEADER.to_string() + "0 40 50 0 0 1236 0 # 1 45 68 10 912 967 90\n")).err().unwrap();
+        assert_eq!((e.line, e.column), (10, 0));
+        assert_eq!(e.kind, ParseErrorKind::MissingLines{expected: String::from("customer lines"), found: 0});
+    }
+}
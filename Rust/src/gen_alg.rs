@@ -1,142 +1,255 @@
-use rand::prelude::*;
-use std::cmp::Ordering;
-use std::i64::MAX;
-use rayon::prelude::*;
-use rayon::iter::once;
-use std::collections::HashSet;
-
-use crate::genome::Genome;
-use crate::selection::tournament_selection;
-use crate::evolution::{crossover, mutate};
-use crate::world::{Depot, Customer};
-
-
-// General constants
-const POP_SIZE: usize = 50;
-const GENERATIONS: i64 = 50_000;
-const CHILDREN: usize = 7;
-const CONVERGENCE_TIME: i64 = 10_000;
-
-pub fn train(input: String) -> (String, String) {
-    let mut repeat_count = 0;
-    let mut last_best = 0;
-    let mut last_pest_penalty = 0;
-
-    let mut rng = thread_rng();
-    let mut penalties = String::new();
-    let mut bests = String::new();
-
-    let mut depots: Vec<Depot> = Vec::new();
-    let mut customers: Vec<Customer> = Vec::new();
-    let vehicles_per_depot = read_input(&mut depots, &mut customers, input);
-    let num_vehicles = vehicles_per_depot as usize * depots.len();
-
-    // Generate population
-    let mut pop: Vec<Genome> = Vec::new();
-    for _ in 0..POP_SIZE {
-        pop.push(Genome::random(customers.len(), num_vehicles, &depots, &customers));
-    }
-
-    // For each generation, do the stuff
-    let mut i = 0;
-    while i < GENERATIONS && (last_pest_penalty > 0 || repeat_count < CONVERGENCE_TIME) { // 
-        let mut new_generation: Vec<Genome> = Vec::new();
-        let gene_pool = tournament_selection(pop, &mut new_generation);
-
-        let random_numbers: Vec<(usize, usize)> = (0..CHILDREN*POP_SIZE/2).into_iter().map(|_| (rng.gen_range(0, gene_pool.len()), rng.gen_range(0, gene_pool.len()))).collect();
-        let new_people: HashSet<Genome> = random_numbers.into_par_iter().flat_map(|(p1, p2)| {
-            let (child1, child2) = crossover(&gene_pool[p1], &gene_pool[p2], &depots, &customers, num_vehicles);
-            let child1 = mutate(child1, &depots, &customers);
-            let child2 = mutate(child2, &depots, &customers);
-            once(child1).chain(once(child2))
-        }).collect();
-
-        for new_p in new_people {
-            new_generation.push(new_p);
-        }
-        new_generation.sort_by(|a, b| match a.get_fitness().partial_cmp(&b.get_fitness()) {None => Ordering::Equal, Some(eq) => eq});
-        new_generation.drain(0..(new_generation.len()-POP_SIZE));
-        // for bla in &new_generation {
-        //     println!("{}0, bla.total_distance());
-        // }
-
-        // To keep track of the progress
-        let (best, valid, total_a, mut best_penalty, worst_penalty, _) = new_generation.iter().fold((MAX, 0, 0, MAX, 0, 0.0), |(mut b, v, t_a, mut b_p, mut w_p, mut b_f), gene| {
-            let d = gene.total_distance();
-            let f = gene.get_fitness();
-            let p = gene.penalty();
-            if f > b_f {b = d; b_f = f;}
-            if p < b_p {b_p = p;}
-            if p > w_p {w_p = p;};
-            (b, if p == 0 {v+1} else {v}, t_a+d, b_p, w_p, b_f)
-        });
-        if best_penalty == MAX {best_penalty = 0;};
-        println!("Gen {}, Individuals: {}, Avg : {}, Best : {}, Valid : {}, Lowest penalty : {}, Highest Penalty : {}", 
-                i + 1, new_generation.len(), total_a/new_generation.len() as i64, best, valid, best_penalty, worst_penalty);
-        bests.push_str(format!("{} ", best).as_str());
-        penalties.push_str(format!("{} ", best_penalty).as_str());
-
-        pop = new_generation;
-        if best == last_best && best_penalty == last_pest_penalty {
-            repeat_count = repeat_count + 1;
-        }
-        else {
-            repeat_count = 0;
-            last_best = best;
-            last_pest_penalty = best_penalty;
-        }
-
-        i = i + 1;
-    }
-
-    // Then take the best individual, and display it
-    let first = pop.pop().unwrap();
-    let mut best = first.clone();
-    while best.penalty() != 0 && pop.len() > 0 {
-        best = pop.pop().unwrap();
-    }
-    if best.penalty() == 0 {
-        return (manage_outputs(best, &depots, &customers), format!("{}\n{}", bests, penalties));
-    }
-    else {
-        return (manage_outputs(first, &depots, &customers), format!("{}\n{}", bests, penalties));
-    }
-}
-
-fn manage_outputs(best: Genome, depots: &Vec<Depot>, customers: &Vec<Customer>) -> String {
-    let mut output = String::new();
-    match Genome::output_result(&best.customer_order, depots, customers) {
-        (_, None) => println!("Gros rip"),
-        (s, Some(d)) => {
-            print!("{}\n{}", d, s);
-            output = format!("{}\n{}", d, s);
-        },
-    }
-    return output
-}
-
-fn read_input(depots: &mut Vec<Depot>, customers: &mut Vec<Customer>, input: String) -> i64 {
-    let data : Vec<Vec<i64>> = input.split('\n').map(|l| l.split_whitespace().map(|n| n.parse::<i64>().unwrap()).collect()).collect();
-    let vehicles_per_depot = data[0][0];
-    let n_customers = data[0][1];
-    let n_depots = data[0][2];
-
-    for i in 1..=n_depots {
-        let max_duration = if data[i as usize][0] == 0 {200} else {data[i as usize][0]};// data[i as usize][0]; //
-        let max_load = data[i as usize][1];
-        let idx = (i + n_depots + n_customers) as usize;
-        let x = data[idx][1];
-        let y = data[idx][2];
-        depots.push(Depot::init(x, y, max_duration, max_load, vehicles_per_depot));
-    }
-
-    for i in (n_depots + 1)..=(n_depots + n_customers) {
-        let j = i as usize;
-        let x = data[j][1];
-        let y = data[j][2];
-        let duration = data[j][3];
-        let load = data[j][4];
-        customers.push(Customer::init(x, y, duration, load));
-    }
-    return vehicles_per_depot
-}
\ No newline at end of file
+use rand::prelude::*;
+use std::cmp::Ordering;
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+use crate::genome::Genome;
+use crate::config::SolverConfig;
+use crate::construction::initial_population;
+use crate::selection::tournament_selection;
+use crate::evolution::breed;
+use crate::local_search::LocalSearch;
+use crate::operators::{Credits, Operators};
+use crate::penalty::adapt_weights;
+use crate::decoder;
+use crate::random;
+use crate::solution::Solution;
+use crate::world::Problem;
+
+
+pub fn train(problem: &Problem, config: &SolverConfig) -> Solution {
+    let mut repeat_count = 0;
+    let mut last_best = 0.0;
+    let mut last_pest_penalty = 0.0;
+
+    let seed = config.seed.unwrap_or_else(random::draw_seed);
+    let mut rng = random::master_rng(seed);
+    let mut best_penalties = Vec::new();
+    let mut best_distances = Vec::new();
+    let local_search = LocalSearch::new(problem, config);
+    let mut operators = Operators::new(problem, config);
+    // Configuration of the generations, whose penalty weights change with config.adaptive_penalty
+    let mut run = config.clone();
+    let mut penalty_weights = Vec::new();
+    // Fittest valid individual of all generations, the last one may have lost it
+    let mut best_valid: Option<Genome> = None;
+
+    // Generate population
+    let mut pop: Vec<Genome> = initial_population(problem, config, &mut rng);
+    sort_by_fitness(&mut pop);
+
+    // For each generation, do the stuff
+    let mut i = 0;
+    while i < config.generations && (last_pest_penalty > 0.0 || repeat_count < config.convergence_time) { // 
+        let mut new_generation: Vec<Genome> = Vec::new();
+        let gene_pool = tournament_selection(pop, &mut new_generation, config, &mut rng);
+
+        let random_numbers: Vec<(usize, usize)> = (0..config.children*config.pop_size/2).map(|_| (rng.gen_range(0, gene_pool.len()), rng.gen_range(0, gene_pool.len()))).collect();
+        let made: Vec<(Genome, Genome, Credits)> = random_numbers.into_par_iter().enumerate().map(|(j, (p1, p2))| {
+            let mut task_rng = random::task_rng(seed, i, j);
+            breed(&gene_pool[p1], &gene_pool[p2], &operators, &local_search, problem, &run, &mut task_rng)
+        }).collect();
+        let mut new_people = Vec::with_capacity(2*made.len());
+        let mut credits = Credits::default();
+        for (child1, child2, child_credits) in made {
+            new_people.push(child1);
+            new_people.push(child2);
+            credits.extend(child_credits);
+        }
+        operators.update(&credits, config);
+
+        // Duplicate children are dropped, keeping the order in which they were made
+        let mut seen = HashSet::new();
+        for new_p in new_people {
+            if seen.insert(new_p.customer_order.clone()) {
+                new_generation.push(new_p);
+            }
+        }
+        // Small instances may not have enough distinct children, random genomes fill the population
+        while new_generation.len() < config.pop_size {
+            new_generation.push(Genome::random(problem, &run, &mut rng));
+        }
+        sort_by_fitness(&mut new_generation);
+        new_generation.drain(0..new_generation.len().saturating_sub(config.pop_size));
+        // for bla in &new_generation {
+        //     println!("{}0, bla.total_distance());
+        // }
+
+        // To keep track of the progress
+        let (best, valid, total_a, mut best_penalty, worst_penalty, _) = new_generation.iter().fold((f64::MAX, 0, 0.0, f64::MAX, 0.0, 0.0), |(mut b, v, t_a, mut b_p, mut w_p, mut b_f), gene| {
+            let d = gene.objective_value();
+            let f = gene.get_fitness();
+            let p = gene.penalty();
+            if f > b_f {b = d; b_f = f;}
+            if p < b_p {b_p = p;}
+            if p > w_p {w_p = p;};
+            (b, if p == 0.0 {v+1} else {v}, t_a+d, b_p, w_p, b_f)
+        });
+        if best_penalty == f64::MAX {best_penalty = 0.0;};
+        let weights = if config.adaptive_penalty {format!(", Load weight : {:.2}, Duration weight : {:.2}", run.load_weight, run.duration_weight)} else {String::new()};
+        if config.verbose {
+            println!("Gen {}, Individuals: {}, Avg : {:.2}, Best : {:.2}, Valid : {}, Lowest penalty : {:.2}, Highest Penalty : {:.2}{}", 
+                    i + 1, new_generation.len(), total_a/new_generation.len() as f64, best, valid, best_penalty, worst_penalty, weights);
+        }
+        best_distances.push(best);
+        best_penalties.push(best_penalty);
+        keep_best_valid(&mut best_valid, &new_generation);
+
+        if config.adaptive_penalty {
+            penalty_weights.push((run.load_weight, run.duration_weight));
+            if adapt_weights(&mut run, new_generation.iter().map(|g| &g.evaluation().violations), repeat_count >= config.penalty_convergence, config) {
+                for genome in new_generation.iter_mut() {
+                    genome.reweigh(&run);
+                }
+                sort_by_fitness(&mut new_generation);
+            }
+        }
+
+        pop = new_generation;
+        if best == last_best && best_penalty == last_pest_penalty {
+            repeat_count = repeat_count + 1;
+        }
+        else {
+            repeat_count = 0;
+            last_best = best;
+            last_pest_penalty = best_penalty;
+        }
+
+        i = i + 1;
+    }
+
+    // Then take the best valid individual, or the fittest one if none is valid
+    let best = match best_valid {
+        Some(b) => b,
+        None => pop.pop().unwrap(),
+    };
+    let mut solution = to_solution(&best, problem, config, seed, best_distances, best_penalties);
+    solution.operator_weights = operators.report(config);
+    solution.penalty_weights = penalty_weights;
+    solution
+}
+
+// The fittest valid individual of a generation sorted by fitness replaces best_valid if it is at least as fit
+fn keep_best_valid(best_valid: &mut Option<Genome>, generation: &[Genome]) {
+    if let Some(valid) = generation.iter().rev().find(|g| g.penalty() == 0.0) {
+        let better = match best_valid {
+            None => true,
+            Some(b) => valid.get_fitness() >= b.get_fitness(),
+        };
+        if better {
+            *best_valid = Some(valid.clone());
+        }
+    }
+}
+
+// Fittest last, as tournament_selection takes its elites from the end
+fn sort_by_fitness(pop: &mut Vec<Genome>) {
+    pop.sort_by(|a, b| match a.get_fitness().partial_cmp(&b.get_fitness()) {None => Ordering::Equal, Some(eq) => eq});
+}
+
+pub fn to_solution(best: &Genome, problem: &Problem, config: &SolverConfig, seed: u64, best_distances: Vec<f64>, best_penalties: Vec<f64>) -> Solution {
+    let routes = decoder::decode(&best.customer_order, problem);
+    let total_distance = routes.iter().map(|r| r.distance).sum();
+    let longest_route = routes.iter().map(|r| r.duration).fold(0.0, f64::max);
+    let pareto_values = if config.pareto {
+        config.pareto_objectives.iter().map(|o| o.value(best.evaluation())).collect()
+    } else {
+        Vec::new()
+    };
+    Solution{
+        routes,
+        total_distance,
+        total_cost: best.total_cost(),
+        longest_route,
+        objective: config.objective,
+        objective_value: best.objective_value(),
+        pareto_values,
+        penalty: best.penalty(),
+        default_max_duration: None,
+        seed,
+        best_distances,
+        best_penalties,
+        operator_weights: Vec::new(),
+        penalty_weights: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Encoding;
+    use crate::file_io;
+    use crate::world::{Customer, Depot};
+
+    // Two customers and two depots only make six distinct giant tours, far fewer than the population
+    #[test]
+    fn a_tiny_instance_keeps_a_full_population() {
+        let depots = vec![Depot::init(0.0, 0.0, 0, 50, 2), Depot::init(30.0, 0.0, 0, 50, 2)];
+        let customers = vec![Customer::init(10.0, 10.0, 0, 5), Customer::init(20.0, 10.0, 0, 5)];
+        let problem = Problem::new(depots, customers);
+        for encoding in [Encoding::Separators, Encoding::GiantTour].iter() {
+            let config = SolverConfig{encoding: *encoding, generations: 20, seed: Some(1), verbose: false, ..SolverConfig::default()};
+            let solution = train(&problem, &config);
+            assert_eq!(solution.penalty, 0.0);
+            assert_eq!(solution.best_distances.len(), 20);
+        }
+    }
+
+    fn p01() -> Problem {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/data/p01");
+        file_io::parse_cordeau(&file_io::read_to_string(path).unwrap()).unwrap()
+    }
+
+    // Elites are the fittest of the population, so the first generation keeps the best individual of the heuristics
+    #[test]
+    fn a_seeded_run_keeps_its_best_seed() {
+        let problem = p01();
+        let config = SolverConfig{frac_savings: 0.2, frac_sweep: 0.2, frac_nearest: 0.2, generations: 1, seed: Some(3), verbose: false, ..SolverConfig::default()};
+        let seeds = initial_population(&problem, &config, &mut random::master_rng(3));
+        let best_seed = seeds.iter().max_by(|a, b| match a.get_fitness().partial_cmp(&b.get_fitness()) {None => Ordering::Equal, Some(eq) => eq}).unwrap();
+        assert_eq!(best_seed.penalty(), 0.0);
+        let solution = train(&problem, &config);
+        assert!(solution.best_distances[0] <= best_seed.objective_value() + 1e-6);
+    }
+
+    // The same seed gives the same run, whatever the number of threads breeding the children
+    #[test]
+    fn a_seed_gives_the_same_run() {
+        let problem = p01();
+        let config = SolverConfig{generations: 30, seed: Some(11), verbose: false, ..SolverConfig::default()};
+        let routes = |s: &Solution| s.routes.iter().map(|r| (r.depot, r.vehicle, r.customers.clone())).collect::<Vec<_>>();
+        let first = train(&problem, &config);
+        let second = train(&problem, &config);
+        let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap().install(|| train(&problem, &config));
+        for other in [&second, &single].iter() {
+            assert_eq!(routes(other), routes(&first));
+            assert_eq!(other.best_distances, first.best_distances);
+            assert_eq!(other.seed, 11);
+        }
+    }
+
+    // A later generation without valid individuals, or whose fittest one is infeasible, keeps the earlier valid one
+    #[test]
+    fn the_best_valid_individual_outlives_fitter_infeasible_ones() {
+        let problem = p01();
+        let config = SolverConfig{frac_savings: 1.0, pop_size: 1, repaired: false, load_weight: 0.001, duration_weight: 0.001, seed: Some(3), verbose: false, ..SolverConfig::default()};
+        let valid = initial_population(&problem, &config, &mut random::master_rng(3)).pop().unwrap();
+        assert_eq!(valid.penalty(), 0.0);
+        // The routes of the first depot joined into its first vehicle are shorter, but overloaded
+        let vehicles = problem.depots()[0].vehicles() as usize;
+        let mut routes: Vec<Vec<i64>> = valid.customer_order.split(|&c| c == 0).map(|r| r.to_vec()).collect();
+        routes[0] = routes[..vehicles].concat();
+        for route in routes[1..vehicles].iter_mut() {
+            route.clear();
+        }
+        let infeasible = Genome::from_routes(routes.join(&0), &problem, &config);
+        assert!(infeasible.penalty() > 0.0);
+        assert!(infeasible.get_fitness() > valid.get_fitness());
+
+        let mut best_valid = None;
+        keep_best_valid(&mut best_valid, std::slice::from_ref(&valid));
+        keep_best_valid(&mut best_valid, std::slice::from_ref(&infeasible));
+        keep_best_valid(&mut best_valid, &[valid.clone(), infeasible]);
+        assert_eq!(best_valid.unwrap().customer_order, valid.customer_order);
+    }
+}
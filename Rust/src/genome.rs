@@ -1,290 +1,248 @@
-use crate::world::{Depot, Customer};
-
-use rand::prelude::*;
-
-use std::hash::{Hash, Hasher};
-
-const REPAIRED: bool = true;
-const ALPHA: f64 = 1000.0;
-
-#[derive(Clone)]
-pub struct Genome {
-    pub customer_order: Vec<i64>,
-    fitness: f64,
-    total_distance: i64,
-    penalty: i64,
-}
-
-impl Genome {
-    // Fitness function and derived stuff
-
-    fn tot_dist(customer_order: &Vec<i64>, depots: &Vec<Depot>, customers: &Vec<Customer>) -> (i64, i64) {
-        let mut penalty = 0;
-        let mut total_distance = 0;
-        
-        let mut depot = 0;
-        let mut vehicle = 0;
-
-        let mut load = 0;
-        let mut duration = 0;
-
-        let mut x = depots[0].x();
-        let mut y = depots[0].y();
-
-        for &c in customer_order {
-            if c == 0 {
-                // Check limits
-                let dist = depots[depot].dist(x, y);
-                duration = duration + dist;
-                total_distance = total_distance + dist;
-                if depots[depot].over_load(load) {
-                    let err = load - depots[depot].max_load();
-                    penalty = penalty + err;
-                }
-                if depots[depot].over_duration(duration) {
-                    let err = duration - depots[depot].max_duration();
-                    penalty = penalty + err;
-                }
-                // Initialize new vehicle :
-                vehicle = vehicle + 1;
-                if vehicle >= depots[depot].vehicles() {
-                    vehicle = 0;
-                    depot = depot + 1;
-                }
-                x = depots[depot].x();
-                y = depots[depot].y();
-                load = 0;
-                duration = 0;
-            }
-            else {
-                match customers.get((c - 1) as usize) {
-                    None => panic!("Wrong customer number : {}", c),
-                    Some(cust) => {
-                        let dist = cust.dist(x, y);
-                        load = load + cust.load();
-                        duration = duration + cust.duration() + dist;
-                        total_distance = total_distance + dist;
-                        x = cust.x();
-                        y = cust.y();
-                    }
-                }
-            }
-        }
-
-        // Check limits
-        let dist = depots[depot].dist(x, y);
-        duration = duration + dist;
-        total_distance = total_distance + dist;
-        if depots[depot].over_load(load) {
-            let err = load - depots[depot].max_load();
-            penalty = penalty + err;
-        }
-        if depots[depot].over_duration(duration) {
-            let err = duration - depots[depot].max_duration();
-            penalty = penalty + err;
-        }
-        (total_distance, penalty)
-    }
-
-    fn repair_load(mut customer_order: Vec<i64>, depots: &Vec<Depot>, customers: &Vec<Customer>) -> Vec<i64> {
-        // println!("From : {}", customer_order.len());
-        // for &n in &customer_order {
-        //     if n == 0 {
-        //         println!("");
-        //     }
-        //     print!("{} ", n);
-        // }
-        // println!("");
-        let mut depot = 0;
-        let mut vehicle = 0;
-
-        let mut load = 0;
-
-        let mut i = 0;
-        while i < customer_order.len() {
-            if customer_order[i] == 0 {
-                // Initialize new vehicle :
-                vehicle = vehicle + 1;
-                if vehicle >= depots[depot].vehicles() {
-                    vehicle = 0;
-                    depot = depot + 1;
-                }
-                load = 0;
-            }
-            else {
-                match customers.get((customer_order[i] - 1) as usize) {
-                    None => panic!("Wrong customer number : {}", customer_order[i]),
-                    Some(cust) => {
-                        load = load + cust.load();
-                    }
-                }
-                if depots[depot].over_load(load) {
-                    let (custoz, worked) = Self::pull_next_zero(i, customer_order);
-                    customer_order = custoz;
-                    if worked {
-                        i = i - 1;
-                    }
-                }
-            }
-            i = i + 1;
-        }
-        // println!("To : {}", customer_order.len());
-        // for &n in &customer_order {
-        //     if n == 0 {
-        //         println!("");
-        //     }
-        //     print!("{} ", n);
-        // }
-        // println!("");
-        customer_order
-    }
-
-    pub fn output_result(customer_order: &Vec<i64>, depots: &Vec<Depot>, customers: &Vec<Customer>) -> (String, Option<i64>) {
-        let mut result_string = String::new();
-        let mut cus_list = String::from("0 ");
-        let mut total_distance = 0;
-        
-        let mut depot = 0;
-        let mut vehicle = 0;
-
-        let mut load = 0;
-        let mut duration = 0;
-
-        let mut x = depots[0].x();
-        let mut y = depots[0].y();
-
-        result_string.push_str(format!("{:<3} {:<3} ", depot+1, vehicle+1).as_str());
-
-        for &c in customer_order {
-            if c == 0 {
-                // Check limits
-                let dist = depots[depot].dist(x, y);
-                duration = duration + dist;
-                total_distance = total_distance + dist;
-                if depots[depot].over_duration(duration) {
-                    println!("Invalid duration");
-                }
-                if depots[depot].over_load(load) {
-                    println!("Invalid load");
-                }
-                cus_list.push_str("0");
-                result_string.push_str(format!("{:<4} {:<4} {}\n", duration, load, cus_list).as_str());
-                // Initialize new vehicle :
-                vehicle = vehicle + 1;
-                if vehicle >= depots[depot].vehicles() {
-                    vehicle = 0;
-                    depot = depot + 1;
-                }
-                x = depots[depot].x();
-                y = depots[depot].y();
-                load = 0;
-                duration = 0;
-                cus_list = String::from("0 ");
-                result_string.push_str(format!("{:<3} {:<3} ", depot+1, vehicle+1).as_str());
-            }
-            else {
-                match customers.get((c - 1) as usize) {
-                    None => panic!("Wrong customer number : {}", c),
-                    Some(cust) => {
-                        let dist = cust.dist(x, y);
-                        load = load + cust.load();
-                        duration = duration + dist;
-                        total_distance = total_distance + dist;
-                        x = cust.x();
-                        y = cust.y();
-                        cus_list.push_str(format!("{} ", c).as_str());
-                    }
-                }
-            }
-        }
-        // Check limits
-        let dist = depots[depot].dist(x, y);
-        duration = duration + dist;
-        total_distance = total_distance + dist;
-        if depots[depot].over_duration(duration) {
-            println!("Invalid duration");
-        }
-        if depots[depot].over_load(load) {
-            println!("Invalid load");
-        }
-        cus_list.push_str("0");
-        result_string.push_str(format!("{:<4} {:<4} {}", duration, load, cus_list).as_str());
-        (result_string, Some(total_distance))
-    }
-
-    // Misc
-
-    pub fn random(n_customers: usize, total_vehicles: usize, depots: &Vec<Depot>, customers: &Vec<Customer>) -> Genome {
-        let mut rng = thread_rng();
-        let mut customer_list: Vec<i64> = (1..=n_customers).map(|n| n as i64).collect();
-        customer_list.shuffle(&mut rng);
-        let step = n_customers/total_vehicles;
-        for i in (1..total_vehicles).rev() {
-            customer_list.insert(step*i, 0);
-        }
-        Self::generate(customer_list, depots, customers)
-    }
-
-    pub fn fitness(total_distance: i64, penalty: i64) -> f64 {
-        1.0/(total_distance as f64 + ALPHA*penalty as f64)
-    }
-
-    pub fn generate(mut customer_order: Vec<i64>, depots: &Vec<Depot>, customers: &Vec<Customer>) -> Genome {
-        if REPAIRED {
-            customer_order = Self::repair_load(customer_order, depots, customers);
-            customer_order.reverse();
-            customer_order = Self::repair_load(customer_order, depots, customers);
-            customer_order.reverse();
-        }
-        let (tot, penalty) = Self::tot_dist(&customer_order, depots, customers);
-        let fit = Self::fitness(tot, penalty);
-        Genome{customer_order, fitness: fit, total_distance: tot, penalty}
-    }
-
-    fn pull_next_zero(i: usize, mut customer_order: Vec<i64>) -> (Vec<i64>, bool) {
-        match customer_order.iter().skip(i).position(|&e| e == 0) {
-            None => return (customer_order, false),
-            Some(a) => {
-                customer_order.remove(a + i);
-                customer_order.insert(i, 0);
-                return (customer_order, true)
-            }
-        }
-    }
-
-    fn _swap_elems(mut customer_order: Vec<i64>, idx1: usize, idx2: usize) -> Vec<i64> {
-        let tmp = customer_order[idx1];
-        customer_order[idx1] = customer_order[idx2];
-        customer_order[idx2] = tmp;
-        return customer_order
-    }
-
-    pub fn get_fitness(&self) -> f64 {
-        self.fitness
-    }
-
-    pub fn penalty(&self) -> i64 {
-        self.penalty
-    }
-
-    pub fn total_distance(&self) -> i64 {
-        self.total_distance
-    }
-}
-
-
-impl Hash for Genome {
-    fn hash<H>(&self, state: &mut H)
-    where
-        H: Hasher,
-    {
-        self.customer_order.hash(state);
-    }
-}
-
-impl PartialEq for Genome {
-    fn eq(&self, other: &Self) -> bool {
-        self.customer_order.eq(&other.customer_order)
-    }
-}
-impl Eq for Genome {}
\ No newline at end of file
+use crate::config::{Encoding, Objective, SolverConfig};
+use crate::decoder::Decoder;
+use crate::solution::{Evaluation, Violations};
+use crate::split::split;
+use crate::world::Problem;
+
+use rand::prelude::*;
+
+use std::hash::{Hash, Hasher};
+
+#[derive(Clone)]
+pub struct Genome {
+    pub customer_order: Vec<i64>,
+    // Giant tour the routes were split from, empty with the separators encoding
+    tour: Vec<i64>,
+    fitness: f64,
+    objective: f64,
+    evaluation: Evaluation,
+}
+
+impl Genome {
+    // Fitness function and derived stuff
+
+    // The cost is the total distance unless vehicles have a fixed or per distance cost
+    pub fn evaluate(customer_order: &[i64], problem: &Problem) -> Evaluation {
+        let mut evaluation = Evaluation::default();
+        let mut shortest_used = f64::MAX;
+        let mut longest_used: f64 = 0.0;
+        for route in Decoder::new(customer_order, problem) {
+            evaluation.total_cost = evaluation.total_cost + route.cost;
+            evaluation.longest_route = evaluation.longest_route.max(route.duration);
+            evaluation.violations = evaluation.violations + route.violations;
+            if !route.customers.is_empty() {
+                evaluation.vehicles_used = evaluation.vehicles_used + 1;
+                shortest_used = shortest_used.min(route.duration);
+                longest_used = longest_used.max(route.duration);
+            }
+        }
+        if evaluation.vehicles_used > 0 {
+            evaluation.imbalance = longest_used - shortest_used;
+        }
+        evaluation
+    }
+
+    pub fn objective(evaluation: &Evaluation, config: &SolverConfig) -> f64 {
+        match config.objective {
+            Objective::Total => evaluation.total_cost,
+            Objective::MinMax => evaluation.longest_route,
+            Objective::Weighted => (1.0 - config.objective_weight)*evaluation.total_cost + config.objective_weight*evaluation.longest_route,
+        }
+    }
+
+    // Maximum load of every vehicle, in the order of their routes in a customer order
+    fn max_loads(problem: &Problem) -> Vec<i64> {
+        problem.depots().iter().flat_map(|d| (0..d.vehicles() as usize).map(move |v| d.vehicle_type(v).max_load())).collect()
+    }
+
+    // Ends a route with the next zero as soon as it goes over the load of its vehicle, then carries on with the following route.
+    // The k-th route of the order is checked against max_loads[k], so that reversed orders can be given reversed loads.
+    fn repair_load(mut customer_order: Vec<i64>, max_loads: &[i64], problem: &Problem) -> Vec<i64> {
+        let (mut i, mut route, mut load) = (0, 0, 0);
+        while i < customer_order.len() {
+            let c = customer_order[i];
+            if c == 0 {
+                route = route + 1;
+                load = 0;
+            }
+            else if load + problem.customer(c).load() > max_loads[route] {
+                let (custoz, worked) = Self::pull_next_zero(i, customer_order);
+                customer_order = custoz;
+                if !worked {
+                    return customer_order
+                }
+                continue;
+            }
+            else {
+                load = load + problem.customer(c).load();
+            }
+            i = i + 1;
+        }
+        customer_order
+    }
+
+    // Misc
+
+    pub fn random<R: Rng>(problem: &Problem, config: &SolverConfig, rng: &mut R) -> Genome {
+        let n_customers = problem.customers().len();
+        let parts = Self::separators(problem, config) + 1;
+        let mut customer_list: Vec<i64> = (1..=n_customers).map(|n| n as i64).collect();
+        customer_list.shuffle(rng);
+        let step = n_customers/parts;
+        for i in (1..parts).rev() {
+            customer_list.insert(step*i, 0);
+        }
+        Self::generate(customer_list, problem, config)
+    }
+
+    // Number of zeros in a chromosome : one between vehicles, or one between depots for giant tours
+    pub fn separators(problem: &Problem, config: &SolverConfig) -> usize {
+        match config.encoding {
+            Encoding::Separators => problem.total_vehicles() - 1,
+            Encoding::GiantTour => problem.depots().len() - 1,
+        }
+    }
+
+    pub fn fitness(objective: f64, violations: &Violations, config: &SolverConfig) -> f64 {
+        1.0/(objective + Self::weighted_penalty(violations, config))
+    }
+
+    pub fn weighted_penalty(violations: &Violations, config: &SolverConfig) -> f64 {
+        config.load_weight*violations.load as f64 + config.duration_weight*violations.duration + config.lateness_weight*violations.lateness
+    }
+
+    // Makes the genome of a chromosome, which is a customer order or a giant tour depending on the encoding
+    pub fn generate(chromosome: Vec<i64>, problem: &Problem, config: &SolverConfig) -> Genome {
+        let (mut customer_order, tour) = match config.encoding {
+            Encoding::Separators => (chromosome, Vec::new()),
+            Encoding::GiantTour => (split(&chromosome, problem, config), chromosome),
+        };
+        if config.repaired && config.encoding == Encoding::Separators {
+            let mut max_loads = Self::max_loads(problem);
+            customer_order = Self::repair_load(customer_order, &max_loads, problem);
+            customer_order.reverse();
+            max_loads.reverse();
+            customer_order = Self::repair_load(customer_order, &max_loads, problem);
+            customer_order.reverse();
+        }
+        let evaluation = Self::evaluate(&customer_order, problem);
+        let objective = Self::objective(&evaluation, config);
+        let fit = Self::fitness(objective, &evaluation.violations, config);
+        Genome{customer_order, tour, fitness: fit, objective, evaluation}
+    }
+
+    // Makes the genome of routes given as a customer order, whatever the encoding
+    pub fn from_routes(customer_order: Vec<i64>, problem: &Problem, config: &SolverConfig) -> Genome {
+        match config.encoding {
+            Encoding::Separators => Self::generate(customer_order, problem, config),
+            Encoding::GiantTour => {
+                let mut tour = Vec::with_capacity(customer_order.len());
+                let mut depot = 0;
+                for route in Decoder::new(&customer_order, problem) {
+                    while depot < route.depot {
+                        tour.push(0);
+                        depot = depot + 1;
+                    }
+                    tour.extend_from_slice(route.customers);
+                }
+                Self::generate(tour, problem, config)
+            },
+        }
+    }
+
+    // What the mutation and crossover operators work on
+    pub fn chromosome(&self) -> &Vec<i64> {
+        if self.tour.is_empty() {&self.customer_order} else {&self.tour}
+    }
+
+    pub fn into_chromosome(self) -> Vec<i64> {
+        if self.tour.is_empty() {self.customer_order} else {self.tour}
+    }
+
+    fn pull_next_zero(i: usize, mut customer_order: Vec<i64>) -> (Vec<i64>, bool) {
+        match customer_order.iter().skip(i).position(|&e| e == 0) {
+            None => return (customer_order, false),
+            Some(a) => {
+                customer_order.remove(a + i);
+                customer_order.insert(i, 0);
+                return (customer_order, true)
+            }
+        }
+    }
+
+    fn _swap_elems(mut customer_order: Vec<i64>, idx1: usize, idx2: usize) -> Vec<i64> {
+        customer_order.swap(idx1, idx2);
+        return customer_order
+    }
+
+    // Scores the genome again once the penalty weights of the configuration changed
+    pub fn reweigh(&mut self, config: &SolverConfig) {
+        self.fitness = Self::fitness(self.objective, &self.evaluation.violations, config);
+    }
+
+    pub fn get_fitness(&self) -> f64 {
+        self.fitness
+    }
+
+    pub fn penalty(&self) -> f64 {
+        self.evaluation.violations.penalty()
+    }
+
+    pub fn evaluation(&self) -> &Evaluation {
+        &self.evaluation
+    }
+
+    pub fn objective_value(&self) -> f64 {
+        self.objective
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.evaluation.total_cost
+    }
+}
+
+
+impl Hash for Genome {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        self.customer_order.hash(state);
+    }
+}
+
+impl PartialEq for Genome {
+    fn eq(&self, other: &Self) -> bool {
+        self.customer_order.eq(&other.customer_order)
+    }
+}
+impl Eq for Genome {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_io;
+    use crate::random;
+    use crate::world::VehicleType;
+
+    // p01 with a small and a large vehicle type at every depot, their capacities differing between depots
+    fn mixed_fleet() -> Problem {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/data/p01");
+        let problem = file_io::parse_cordeau(&file_io::read_to_string(path).unwrap()).unwrap();
+        let fleets = (0..problem.depots().len() as i64).map(|d| vec![VehicleType::init(0, 30 + 10*d, 0.0, 1.0, 2), VehicleType::init(0, 120 - 10*d, 0.0, 1.0, 2)]).collect();
+        problem.with_fleets(fleets)
+    }
+
+    #[test]
+    fn repair_never_adds_load_on_a_mixed_fleet() {
+        let problem = mixed_fleet();
+        let repaired = SolverConfig::default();
+        let raw = SolverConfig{repaired: false, ..SolverConfig::default()};
+        let mut rng = random::master_rng(0);
+        for _ in 0..500 {
+            let order = Genome::random(&problem, &raw, &mut rng).into_chromosome();
+            let before = Genome::generate(order.clone(), &problem, &raw).evaluation().violations.load;
+            let after = Genome::generate(order, &problem, &repaired).evaluation().violations.load;
+            assert!(after <= before, "{} > {}", after, before);
+        }
+    }
+}
@@ -0,0 +1,28 @@
+#![allow(clippy::assign_op_pattern, clippy::needless_return, clippy::ptr_arg)]
+
+pub mod config;
+pub mod file_io;
+pub mod solution;
+pub mod solver;
+pub mod validator;
+pub mod world;
+
+mod assignment;
+mod construction;
+mod decoder;
+mod evolution;
+mod gen_alg;
+mod genome;
+mod local_search;
+mod nsga2;
+mod operators;
+mod penalty;
+mod random;
+mod selection;
+mod split;
+
+pub use config::{Assignment, ConfigError, Encoding, Improvement, Objective, OperatorSelection, ParetoObjective, SolverConfig};
+pub use solution::{Evaluation, Route, Solution, Violations};
+pub use solver::Solver;
+pub use validator::{validate, ValidationReport};
+pub use world::{Customer, Depot, Problem, Rounding, TimeWindow, VehicleType};
@@ -0,0 +1,427 @@
+use rand::prelude::*;
+
+use crate::config::{Improvement, SolverConfig};
+use crate::decoder::{evaluate_route, Decoder};
+use crate::genome::Genome;
+use crate::world::Problem;
+
+// Moves that don't lower the score by more than this are ignored, so that the search always ends
+const MIN_GAIN: f64 = 1e-9;
+const MAX_SEGMENT: usize = 3;
+const MAX_PIECES: usize = 5;
+
+struct Route {
+    depot: usize,
+    vehicle: usize,
+    customers: Vec<i64>,
+    // Distance from the first customer to each customer of the route
+    along: Vec<f64>,
+    score: f64,
+}
+
+// Customers from..to of a route, possibly reversed
+#[derive(Clone, Copy)]
+struct Piece {
+    route: usize,
+    from: usize,
+    to: usize,
+    reversed: bool,
+}
+
+impl Piece {
+    fn new(route: usize, from: usize, to: usize) -> Piece {
+        Piece{route, from, to, reversed: false}
+    }
+
+    fn reversed(route: usize, from: usize, to: usize) -> Piece {
+        Piece{route, from, to, reversed: true}
+    }
+}
+
+// New pieces of a route, the unused ones are empty
+#[derive(Clone, Copy)]
+struct Change {
+    route: usize,
+    pieces: [Piece; MAX_PIECES],
+}
+
+impl Change {
+    fn new(route: usize, pieces: &[Piece]) -> Change {
+        let mut all = [Piece::new(route, 0, 0); MAX_PIECES];
+        all[..pieces.len()].copy_from_slice(pieces);
+        Change{route, pieces: all}
+    }
+}
+
+// A move, as the changes of one or two routes
+struct Candidate {
+    changes: [Change; 2],
+    len: usize,
+}
+
+impl Candidate {
+    fn one(change: Change) -> Candidate {
+        Candidate{changes: [change, change], len: 1}
+    }
+
+    fn two(first: Change, second: Change) -> Candidate {
+        Candidate{changes: [first, second], len: 2}
+    }
+
+    fn changes(&self) -> &[Change] {
+        &self.changes[..self.len]
+    }
+}
+
+struct Move {
+    gain: f64,
+    changes: Vec<(usize, Vec<i64>, f64)>,
+}
+
+// Education step of the memetic algorithm. Moves only bring a customer next to one of its nearest customers
+// (or into an empty route), and are scored on the cost of the routes plus their weighted penalty.
+pub struct LocalSearch {
+    neighbours: Vec<Vec<i64>>,
+}
+
+impl LocalSearch {
+    pub fn new(problem: &Problem, config: &SolverConfig) -> LocalSearch {
+        let n = problem.customers().len() as i64;
+        let neighbours = (1..=n).map(|u| {
+            let mut others: Vec<i64> = (1..=n).filter(|&v| v != u).collect();
+            let node = Problem::customer_node(u);
+            others.sort_by(|&a, &b| {
+                let da = problem.distance(node, Problem::customer_node(a));
+                let db = problem.distance(node, Problem::customer_node(b));
+                da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+            });
+            others.truncate(config.ls_neighbours);
+            others
+        }).collect();
+        LocalSearch{neighbours}
+    }
+
+    // Improves the child with probability config.prob_education
+    pub fn educate<R: Rng>(&self, child: Genome, problem: &Problem, config: &SolverConfig, rng: &mut R) -> Genome {
+        let educate: f64 = rng.gen();
+        if educate >= config.prob_education {
+            return child
+        }
+        let (mut routes, mut positions) = routes(&child, problem, config);
+
+        let mut order: Vec<i64> = (1..=problem.customers().len() as i64).collect();
+        loop {
+            order.shuffle(rng);
+            if !self.improve(&mut routes, &mut positions, &order, problem, config) {
+                break;
+            }
+        }
+
+        let mut customer_order = Vec::with_capacity(child.customer_order.len());
+        for (i, route) in routes.iter().enumerate() {
+            if i > 0 {
+                customer_order.push(0);
+            }
+            customer_order.extend_from_slice(&route.customers);
+        }
+        Genome::from_routes(customer_order, problem, config)
+    }
+
+    // One pass over the customers, applying the first or the best improving move around each of them
+    fn improve(&self, routes: &mut Vec<Route>, positions: &mut Vec<(usize, usize)>, order: &[i64], problem: &Problem, config: &SolverConfig) -> bool {
+        let mut improved = false;
+        for &u in order {
+            let mut found: Option<Move> = None;
+            self.moves(routes, positions, u, &mut |candidate| {
+                let floor = found.as_ref().map_or(MIN_GAIN, |m| m.gain);
+                if let Some(m) = evaluate(routes, candidate, problem, config, floor) {
+                    found = Some(m);
+                    return config.improvement == Improvement::First
+                }
+                false
+            });
+            if let Some(m) = found {
+                apply(routes, positions, m, problem);
+                improved = true;
+            }
+        }
+        improved
+    }
+
+    // Gives every move around customer u to visit, until visit returns true
+    fn moves(&self, routes: &[Route], positions: &[(usize, usize)], u: i64, visit: &mut dyn FnMut(Candidate) -> bool) {
+        let (ru, iu) = positions[u as usize - 1];
+        let len_u = routes[ru].customers.len();
+        for &v in &self.neighbours[u as usize - 1] {
+            let (rv, iv) = positions[v as usize - 1];
+            let len_v = routes[rv].customers.len();
+
+            // Relocate, or Or-opt within a route : a segment starting at u goes after v
+            for len in 1..=MAX_SEGMENT.min(len_u - iu) {
+                let segment = Piece::new(ru, iu, iu + len);
+                let candidate = if ru != rv {
+                    Candidate::two(
+                        Change::new(ru, &[Piece::new(ru, 0, iu), Piece::new(ru, iu + len, len_u)]),
+                        Change::new(rv, &[Piece::new(rv, 0, iv + 1), segment, Piece::new(rv, iv + 1, len_v)]),
+                    )
+                }
+                else if iv + 1 < iu {
+                    Candidate::one(Change::new(ru, &[Piece::new(ru, 0, iv + 1), segment, Piece::new(ru, iv + 1, iu), Piece::new(ru, iu + len, len_u)]))
+                }
+                else if iv >= iu + len {
+                    Candidate::one(Change::new(ru, &[Piece::new(ru, 0, iu), Piece::new(ru, iu + len, iv + 1), segment, Piece::new(ru, iv + 1, len_u)]))
+                }
+                else {
+                    // v is already just before u, or inside the segment
+                    break;
+                };
+                if visit(candidate) {
+                    return
+                }
+            }
+
+            // Swap u and v
+            let candidate = if ru != rv {
+                Candidate::two(
+                    Change::new(ru, &[Piece::new(ru, 0, iu), Piece::new(rv, iv, iv + 1), Piece::new(ru, iu + 1, len_u)]),
+                    Change::new(rv, &[Piece::new(rv, 0, iv), Piece::new(ru, iu, iu + 1), Piece::new(rv, iv + 1, len_v)]),
+                )
+            }
+            else {
+                let (i, j) = if iu < iv {(iu, iv)} else {(iv, iu)};
+                Candidate::one(Change::new(ru, &[Piece::new(ru, 0, i), Piece::new(ru, j, j + 1), Piece::new(ru, i + 1, j), Piece::new(ru, i, i + 1), Piece::new(ru, j + 1, len_u)]))
+            };
+            if visit(candidate) {
+                return
+            }
+
+            let candidate = if ru == rv {
+                // 2-opt : reversing the customers after u up to v makes them adjacent
+                if iu >= iv {
+                    continue;
+                }
+                Candidate::one(Change::new(ru, &[Piece::new(ru, 0, iu + 1), Piece::reversed(ru, iu + 1, iv + 1), Piece::new(ru, iv + 1, len_u)]))
+            }
+            else {
+                // 2-opt* : the routes exchange their ends after u and after v, even between depots
+                Candidate::two(
+                    Change::new(ru, &[Piece::new(ru, 0, iu + 1), Piece::new(rv, iv + 1, len_v)]),
+                    Change::new(rv, &[Piece::new(rv, 0, iv + 1), Piece::new(ru, iu + 1, len_u)]),
+                )
+            };
+            if visit(candidate) {
+                return
+            }
+        }
+
+        // Relocate u alone into the first empty route of every depot
+        let mut tried = Vec::new();
+        for (r, route) in routes.iter().enumerate() {
+            if route.customers.is_empty() && !tried.contains(&route.depot) {
+                tried.push(route.depot);
+                let candidate = Candidate::two(
+                    Change::new(ru, &[Piece::new(ru, 0, iu), Piece::new(ru, iu + 1, len_u)]),
+                    Change::new(r, &[Piece::new(ru, iu, iu + 1)]),
+                );
+                if visit(candidate) {
+                    return
+                }
+            }
+        }
+    }
+}
+
+// Routes of a genome, and the route and index of each customer
+fn routes(genome: &Genome, problem: &Problem, config: &SolverConfig) -> (Vec<Route>, Vec<(usize, usize)>) {
+    let routes: Vec<Route> = Decoder::new(&genome.customer_order, problem).map(|r| {
+        let customers = r.customers.to_vec();
+        let along = along(problem, &customers);
+        let score = score(problem, config, r.depot, r.vehicle, &customers);
+        Route{depot: r.depot, vehicle: r.vehicle, customers, along, score}
+    }).collect();
+    let mut positions = vec![(0, 0); problem.customers().len()];
+    for r in 0..routes.len() {
+        locate(&routes, r, &mut positions);
+    }
+    (routes, positions)
+}
+
+fn score(problem: &Problem, config: &SolverConfig, depot: usize, vehicle: usize, customers: &[i64]) -> f64 {
+    let stats = evaluate_route(problem, depot, vehicle, customers);
+    stats.cost + Genome::weighted_penalty(&stats.violations, config)
+}
+
+fn along(problem: &Problem, customers: &[i64]) -> Vec<f64> {
+    let mut along = Vec::with_capacity(customers.len());
+    let mut total = 0.0;
+    for (i, &c) in customers.iter().enumerate() {
+        if i > 0 {
+            total = total + problem.distance(Problem::customer_node(customers[i - 1]), Problem::customer_node(c));
+        }
+        along.push(total);
+    }
+    along
+}
+
+// Distance of the route made of the pieces, from the prefix distances of the routes they come from.
+// Distances are symmetric, so a reversed piece keeps its length.
+fn distance(routes: &[Route], depot_node: usize, pieces: &[Piece], problem: &Problem) -> f64 {
+    let mut node = depot_node;
+    let mut total = 0.0;
+    for p in pieces {
+        if p.from >= p.to {
+            continue;
+        }
+        let route = &routes[p.route];
+        let (first, last) = if p.reversed {(p.to - 1, p.from)} else {(p.from, p.to - 1)};
+        total = total + problem.distance(node, Problem::customer_node(route.customers[first]));
+        total = total + route.along[p.to - 1] - route.along[p.from];
+        node = Problem::customer_node(route.customers[last]);
+    }
+    total + problem.distance(node, depot_node)
+}
+
+// The move if it gains more than floor. Penalties can't be negative, so the cost of the new routes bounds
+// the gain and most moves are dropped without driving their routes.
+fn evaluate(routes: &[Route], candidate: Candidate, problem: &Problem, config: &SolverConfig, floor: f64) -> Option<Move> {
+    let mut bound = 0.0;
+    for change in candidate.changes() {
+        let route = &routes[change.route];
+        let empty = change.pieces.iter().all(|p| p.from >= p.to);
+        let distance = distance(routes, problem.depot_node(route.depot), &change.pieces, problem);
+        let vehicle_type = problem.depots()[route.depot].vehicle_type(route.vehicle);
+        bound = bound + route.score - vehicle_type.route_cost(distance, empty);
+    }
+    if bound <= floor {
+        return None
+    }
+
+    let mut gain = 0.0;
+    let changes: Vec<(usize, Vec<i64>, f64)> = candidate.changes().iter().map(|change| {
+        let r = change.route;
+        let customers = customers(routes, change);
+        let new_score = score(problem, config, routes[r].depot, routes[r].vehicle, &customers);
+        gain = gain + routes[r].score - new_score;
+        (r, customers, new_score)
+    }).collect();
+    if gain <= floor {
+        return None
+    }
+    Some(Move{gain, changes})
+}
+
+// Customers of the route a change makes
+fn customers(routes: &[Route], change: &Change) -> Vec<i64> {
+    let mut customers = Vec::new();
+    for p in &change.pieces {
+        let slice = &routes[p.route].customers[p.from..p.to];
+        if p.reversed {
+            customers.extend(slice.iter().rev());
+        }
+        else {
+            customers.extend_from_slice(slice);
+        }
+    }
+    customers
+}
+
+fn apply(routes: &mut [Route], positions: &mut [(usize, usize)], m: Move, problem: &Problem) {
+    for (r, customers, score) in m.changes {
+        routes[r].along = along(problem, &customers);
+        routes[r].customers = customers;
+        routes[r].score = score;
+        locate(routes, r, positions);
+    }
+}
+
+// Records the route and index of the customers of route r
+fn locate(routes: &[Route], r: usize, positions: &mut [(usize, usize)]) {
+    for (i, &c) in routes[r].customers.iter().enumerate() {
+        positions[c as usize - 1] = (r, i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Encoding;
+    use crate::file_io;
+    use crate::random;
+
+    fn problem() -> Problem {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/data/p01");
+        file_io::parse_cordeau(&file_io::read_to_string(path).unwrap()).unwrap()
+    }
+
+    // Random genomes, half of them with their customers in the first routes so that the others are empty
+    fn genomes(problem: &Problem, config: &SolverConfig) -> Vec<Genome> {
+        let mut rng = random::master_rng(0);
+        (0..20).map(|k| {
+            let genome = Genome::random(problem, config, &mut rng);
+            if k % 2 == 0 {
+                return genome
+            }
+            let customers: Vec<i64> = genome.customer_order.iter().copied().filter(|&c| c != 0).collect();
+            let mut order = customers.chunks(7).collect::<Vec<&[i64]>>().join(&0);
+            while order.len() < customers.len() + problem.total_vehicles() - 1 {
+                order.push(0);
+            }
+            Genome::from_routes(order, problem, config)
+        }).collect()
+    }
+
+    // Every move keeps each customer exactly once in as many routes, and its distance is the one of its routes
+    #[test]
+    fn moves_keep_a_permutation_and_their_distance() {
+        let problem = problem();
+        let config = SolverConfig{repaired: false, ..SolverConfig::default()};
+        let search = LocalSearch::new(&problem, &config);
+        let (mut reversed, mut within, mut between, mut into_empty) = (0, 0, 0, 0);
+        for genome in genomes(&problem, &config) {
+            let (routes, positions) = routes(&genome, &problem, &config);
+            for u in 1..=problem.customers().len() as i64 {
+                search.moves(&routes, &positions, u, &mut |candidate| {
+                    let mut after: Vec<Vec<i64>> = routes.iter().map(|r| r.customers.clone()).collect();
+                    for change in candidate.changes() {
+                        let route = &routes[change.route];
+                        let customers = customers(&routes, change);
+                        let full = evaluate_route(&problem, route.depot, route.vehicle, &customers).distance;
+                        let incremental = distance(&routes, problem.depot_node(route.depot), &change.pieces, &problem);
+                        assert!((full - incremental).abs() < 1e-6);
+                        if route.customers.is_empty() {
+                            into_empty = into_empty + 1;
+                        }
+                        if change.pieces.iter().any(|p| p.reversed && p.from < p.to) {
+                            reversed = reversed + 1;
+                        }
+                        after[change.route] = customers;
+                    }
+                    if candidate.changes().len() == 1 {within = within + 1} else {between = between + 1}
+                    let mut all: Vec<i64> = after.concat();
+                    all.sort_unstable();
+                    assert_eq!(all, (1..=problem.customers().len() as i64).collect::<Vec<i64>>());
+                    assert_eq!(after.len(), problem.total_vehicles());
+                    false
+                });
+            }
+        }
+        assert!(reversed > 0 && within > 0 && between > 0 && into_empty > 0);
+    }
+
+    #[test]
+    fn education_never_makes_a_child_worse() {
+        let problem = problem();
+        for encoding in [Encoding::Separators, Encoding::GiantTour].iter() {
+            for &repaired in [false, true].iter() {
+                let config = SolverConfig{encoding: *encoding, repaired, prob_education: 1.0, ..SolverConfig::default()};
+                let search = LocalSearch::new(&problem, &config);
+                let mut rng = random::master_rng(1);
+                for genome in genomes(&problem, &config) {
+                    let fitness = genome.get_fitness();
+                    let educated = search.educate(genome, &problem, &config, &mut rng);
+                    assert!(educated.get_fitness() >= fitness*(1.0 - 1e-9), "{:?} repaired {}", encoding, repaired);
+                }
+            }
+        }
+    }
+}
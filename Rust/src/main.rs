@@ -1,29 +1,163 @@
-mod gen_alg;
-mod evolution;
-mod genome;
-mod selection;
-mod world;
-mod file_io;
+#![allow(clippy::assign_op_pattern, clippy::needless_return, clippy::ptr_arg)]
 
+mod cli;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
 use std::time::SystemTime;
 
+use cli::{Command, RunArgs};
+use genetic_routing_problem::{file_io, validator, Problem, Solution, Solver};
+
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = match cli::parse(&args) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            process::exit(2);
+        },
+    };
+
+    let result = match command {
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        },
+        Command::Solve(run) | Command::Batch(run) => solve_all(&run),
+        Command::Validate(run) => validate_all(&run),
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
 
-    for i in 1..=1 {
+fn solve_all(run: &RunArgs) -> Result<(), String> {
+    fs::create_dir_all(&run.output_dir).map_err(|e| format!("couldn't create {}: {}", run.output_dir.display(), e))?;
+
+    for path in &run.instances {
         let begin = SystemTime::now();
-        println!("Problem {}", i);
-        let (output, graphing) = gen_alg::train(file_io::read_to_string(format!("src/data/p{:02}", i).as_str()));
-        file_io::write_to_file(format!("results/result_p{}.txt", i).as_str(), output.as_str()).expect("Couldn't write to file");
-        file_io::write_to_file(format!("results/graph_data_p{}.txt", i).as_str(), graphing.as_str()).expect("Couldn't write to file");
-    
+        let name = instance_name(path);
+        println!("Problem {}", name);
+        let problem = read_instance(path, run)?;
+        if run.config.pareto {
+            solve_pareto(&problem, &name, run)?;
+        }
+        else {
+            solve_single(&problem, &name, run)?;
+        }
+
         println!("\nTime elapsed in minutes and seconds : {}m{}s", begin.elapsed().unwrap().as_secs()/60, begin.elapsed().unwrap().as_secs()%60);
     }
+    Ok(())
+}
+
+fn solve_single(problem: &Problem, name: &str, run: &RunArgs) -> Result<(), String> {
+    let solution = Solver::solve(problem, &run.config);
+    println!("Seed : {}", solution.seed);
+    println!("Objective : {} = {:.2} (total cost {:.2}, longest route {:.2})", solution.objective, solution.objective_value, solution.total_cost, solution.longest_route);
+    if let Some(max_duration) = solution.default_max_duration {
+        println!("Depots without a duration limit were given a maximum route duration of {}", max_duration);
+    }
+    print_operator_weights(&solution);
+    for route in &solution.routes {
+        if route.violations.duration > 0.0 {
+            println!("Invalid duration");
+        }
+        if route.violations.load > 0 {
+            println!("Invalid load");
+        }
+        if route.violations.lateness > 0.0 {
+            println!("Invalid time window");
+        }
+    }
+    let output = solution.result_text();
+    let graphing = solution.convergence_text();
+    print!("{}", output);
+
+    let result_path = run.output_dir.join(format!("result_{}.txt", name));
+    let graph_path = run.output_dir.join(format!("graph_data_{}.txt", name));
+    write(&result_path, &output)?;
+    write(&graph_path, &graphing)
+}
+
+fn print_operator_weights(solution: &Solution) {
+    if !solution.operator_weights.is_empty() {
+        let weights: Vec<String> = solution.operator_weights.iter().map(|(name, p)| format!("{} {:.3}", name, p)).collect();
+        println!("Operator weights : {}", weights.join(", "));
+    }
+}
+
+// Every solution of the front gets its own result file, numbered like the lines of the summary
+fn solve_pareto(problem: &Problem, name: &str, run: &RunArgs) -> Result<(), String> {
+    let front = Solver::solve_pareto(problem, &run.config);
+    if let Some(first) = front.first() {
+        println!("Seed : {}", first.seed);
+        if let Some(max_duration) = first.default_max_duration {
+            println!("Depots without a duration limit were given a maximum route duration of {}", max_duration);
+        }
+        print_operator_weights(first);
+        write(&run.output_dir.join(format!("graph_data_{}.txt", name)), &first.convergence_text())?;
+    }
+    let summary = Solution::pareto_text(&front, &run.config.pareto_objectives);
+    println!("Pareto front of {} solutions :\n{}", front.len(), summary);
+    for (k, solution) in front.iter().enumerate() {
+        write(&run.output_dir.join(format!("result_{}_{}.txt", name, k + 1)), &solution.result_text())?;
+    }
+    write(&run.output_dir.join(format!("pareto_{}.txt", name)), &summary)
+}
 
-    // let begin = SystemTime::now();
-    // let num = 1;
-    // let (output, graphing) = gen_alg::train(file_io::read_to_string(format!("src/data/p{:02}", num).as_str()));
-    // file_io::write_to_file(format!("results/result_p{}.txt", num).as_str(), output.as_str()).expect("Couldn't write to file");
-    // file_io::write_to_file(format!("results/graph_data_p{}.txt", num).as_str(), graphing.as_str()).expect("Couldn't write to file");
-    
-    // println!("\nTime elapsed in minutes and seconds : {}m{}s", begin.elapsed().unwrap().as_secs()/60, begin.elapsed().unwrap().as_secs()%60);
-}
\ No newline at end of file
+fn write(path: &Path, text: &str) -> Result<(), String> {
+    file_io::write_to_file(path, text).map_err(|e| format!("couldn't write {}: {}", path.display(), e))
+}
+
+fn validate_all(run: &RunArgs) -> Result<(), String> {
+    if let Some(solution_path) = &run.solution {
+        return validate_solution(&run.instances[0], solution_path, run)
+    }
+    for path in &run.instances {
+        let problem = read_instance(path, run)?;
+        println!("{} : {} depots, {} customers, {} vehicles", path.display(), problem.depots().len(), problem.customers().len(), problem.total_vehicles());
+    }
+    Ok(())
+}
+
+// Distances follow the rounding policy of the configuration, like when the solution was computed
+fn validate_solution(instance_path: &Path, solution_path: &Path, run: &RunArgs) -> Result<(), String> {
+    let mut problem = read_instance(instance_path, run)?.with_rounding(run.config.distance_rounding);
+    if let Some(max_duration) = run.config.default_max_duration {
+        problem = problem.with_default_max_duration(max_duration);
+    }
+    let input = file_io::read_to_string(solution_path).map_err(|e| format!("couldn't read {}: {}", solution_path.display(), e))?;
+    let result = file_io::parse_result(&input).map_err(|e| format!("couldn't parse {}: {}", solution_path.display(), e))?;
+
+    let report = validator::validate(&problem, &result);
+    println!("{}", serde_json::to_string_pretty(&report).expect("the report is always serializable"));
+    if !report.valid {
+        return Err(format!("{} is not a valid solution of {}", solution_path.display(), instance_path.display()))
+    }
+    Ok(())
+}
+
+fn read_instance(path: &Path, run: &RunArgs) -> Result<Problem, String> {
+    let input = file_io::read_to_string(path).map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+    let problem = file_io::parse_instance(&input).map_err(|e| format!("couldn't parse {}: {}", path.display(), e))?;
+    match &run.fleet {
+        None => Ok(problem),
+        Some(fleet_path) => {
+            let input = file_io::read_to_string(fleet_path).map_err(|e| format!("couldn't read {}: {}", fleet_path.display(), e))?;
+            let fleets = file_io::parse_fleet(&input, problem.depots().len()).map_err(|e| format!("couldn't parse {}: {}", fleet_path.display(), e))?;
+            Ok(problem.with_fleets(fleets))
+        },
+    }
+}
+
+fn instance_name(path: &Path) -> String {
+    match path.file_stem() {
+        None => String::from("instance"),
+        Some(stem) => stem.to_string_lossy().replace(' ', "_"),
+    }
+}
@@ -0,0 +1,293 @@
+use rand::prelude::*;
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use crate::config::SolverConfig;
+use crate::construction::initial_population;
+use crate::evolution::breed;
+use crate::local_search::LocalSearch;
+use crate::operators::{Credits, Operators};
+use crate::penalty::adapt_weights;
+use crate::gen_alg::to_solution;
+use crate::genome::Genome;
+use crate::random;
+use crate::solution::Solution;
+use crate::world::Problem;
+
+// An individual with its objective values, the weighted penalty of its violations,
+// and its front and crowding distance once the population is sorted
+struct Member {
+    genome: Genome,
+    values: Vec<f64>,
+    violation: f64,
+    rank: usize,
+    crowding: f64,
+}
+
+impl Member {
+    fn new(genome: Genome, config: &SolverConfig) -> Member {
+        let values = config.pareto_objectives.iter().map(|o| o.value(genome.evaluation())).collect();
+        let violation = Genome::weighted_penalty(&genome.evaluation().violations, config);
+        Member{genome, values, violation, rank: 0, crowding: 0.0}
+    }
+
+    // Constrained domination : a feasible individual dominates an infeasible one,
+    // infeasible ones are compared by violation and feasible ones by Pareto dominance
+    fn dominates(&self, other: &Member) -> bool {
+        if self.violation != other.violation {
+            return self.violation < other.violation
+        }
+        let mut better = false;
+        for (a, b) in self.values.iter().zip(&other.values) {
+            if a > b {
+                return false
+            }
+            if a < b {
+                better = true;
+            }
+        }
+        better
+    }
+
+    // Crowded comparison, lower rank first then higher crowding distance
+    fn compare(&self, other: &Member) -> Ordering {
+        match self.rank.cmp(&other.rank) {
+            Ordering::Equal => other.crowding.partial_cmp(&self.crowding).unwrap_or(Ordering::Equal),
+            ord => ord,
+        }
+    }
+}
+
+// Runs NSGA-II with the mutation and crossover operators of the genetic algorithm and returns the
+// first front of the last population, sorted by the first objective
+pub fn train_pareto(problem: &Problem, config: &SolverConfig) -> Vec<Solution> {
+    let seed = config.seed.unwrap_or_else(random::draw_seed);
+    let mut rng = random::master_rng(seed);
+    let mut best_penalties = Vec::new();
+    let mut best_distances = Vec::new();
+    let local_search = LocalSearch::new(problem, config);
+    let mut operators = Operators::new(problem, config);
+    // Configuration of the generations, whose penalty weights change with config.adaptive_penalty
+    let mut run = config.clone();
+    let mut penalty_weights = Vec::new();
+
+    let mut pop: Vec<Member> = initial_population(problem, config, &mut rng).into_iter().map(|g| Member::new(g, config)).collect();
+    sort_population(&mut pop);
+
+    let mut repeat_count = 0;
+    let mut last_front: Vec<Vec<f64>> = Vec::new();
+    let mut i = 0;
+    while i < config.generations && repeat_count < config.convergence_time {
+        // Binary tournaments on the crowded comparison pick the parents
+        let parents: Vec<(usize, usize)> = (0..config.children*config.pop_size/2).map(|_| {
+            (binary_tournament(&pop, &mut rng), binary_tournament(&pop, &mut rng))
+        }).collect();
+        let made: Vec<(Genome, Genome, Credits)> = parents.into_par_iter().enumerate().map(|(j, (p1, p2))| {
+            let mut task_rng = random::task_rng(seed, i, j);
+            breed(&pop[p1].genome, &pop[p2].genome, &operators, &local_search, problem, &run, &mut task_rng)
+        }).collect();
+        let mut children = Vec::with_capacity(2*made.len());
+        let mut credits = Credits::default();
+        for (child1, child2, child_credits) in made {
+            children.push(child1);
+            children.push(child2);
+            credits.extend(child_credits);
+        }
+        operators.update(&credits, config);
+
+        // Parents and children compete for the next population, duplicates are dropped
+        let mut seen: HashSet<Vec<i64>> = pop.iter().map(|m| m.genome.customer_order.clone()).collect();
+        for child in children {
+            if seen.insert(child.customer_order.clone()) {
+                pop.push(Member::new(child, &run));
+            }
+        }
+        sort_population(&mut pop);
+        pop.truncate(config.pop_size);
+
+        let front: Vec<&Member> = pop.iter().filter(|m| m.rank == 0).collect();
+        let best_cost = pop.iter().map(|m| m.genome.total_cost()).fold(f64::MAX, f64::min);
+        let best_penalty = pop.iter().map(|m| m.genome.penalty()).fold(f64::MAX, f64::min);
+        let valid = pop.iter().filter(|m| m.genome.penalty() == 0.0).count();
+        let weights = if config.adaptive_penalty {format!(", Load weight : {:.2}, Duration weight : {:.2}", run.load_weight, run.duration_weight)} else {String::new()};
+        if config.verbose {
+            println!("Gen {}, Individuals: {}, Front : {}, Valid : {}, Lowest cost : {:.2}, Lowest penalty : {:.2}{}",
+                    i + 1, pop.len(), front.len(), valid, best_cost, best_penalty, weights);
+        }
+        best_distances.push(best_cost);
+        best_penalties.push(best_penalty);
+
+        // The run has converged once the values of the first front stop changing
+        let mut front_values: Vec<Vec<f64>> = front.iter().map(|m| m.values.clone()).collect();
+        front_values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        if front_values == last_front {
+            repeat_count = repeat_count + 1;
+        }
+        else {
+            repeat_count = 0;
+            last_front = front_values;
+        }
+
+        // Infeasible members are compared on their weighted violation, so it follows the weights
+        if config.adaptive_penalty {
+            penalty_weights.push((run.load_weight, run.duration_weight));
+            if adapt_weights(&mut run, pop.iter().map(|m| &m.genome.evaluation().violations), repeat_count >= config.penalty_convergence, config) {
+                for m in pop.iter_mut() {
+                    m.genome.reweigh(&run);
+                    m.violation = Genome::weighted_penalty(&m.genome.evaluation().violations, &run);
+                }
+                sort_population(&mut pop);
+            }
+        }
+
+        i = i + 1;
+    }
+
+    // Members with the same objective values are the same point of the front
+    let mut front: Vec<Member> = pop.into_iter().filter(|m| m.rank == 0).collect();
+    front.sort_by(|a, b| a.values.partial_cmp(&b.values).unwrap_or(Ordering::Equal));
+    front.dedup_by(|a, b| a.values == b.values);
+    let operator_weights = operators.report(config);
+    front.iter().map(|m| {
+        let mut solution = to_solution(&m.genome, problem, config, seed, best_distances.clone(), best_penalties.clone());
+        solution.operator_weights = operator_weights.clone();
+        solution.penalty_weights = penalty_weights.clone();
+        solution
+    }).collect()
+}
+
+fn binary_tournament<R: Rng>(pop: &[Member], rng: &mut R) -> usize {
+    let a = rng.gen_range(0, pop.len());
+    let b = rng.gen_range(0, pop.len());
+    if pop[b].compare(&pop[a]) == Ordering::Less {b} else {a}
+}
+
+// Fast non-dominated sort, then crowding distance within each front. The population ends up in crowded comparison order.
+fn sort_population(pop: &mut Vec<Member>) {
+    let n = pop.len();
+    let mut dominated: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut domination_count = vec![0; n];
+    for p in 0..n {
+        for q in (p + 1)..n {
+            if pop[p].dominates(&pop[q]) {
+                dominated[p].push(q);
+                domination_count[q] = domination_count[q] + 1;
+            }
+            else if pop[q].dominates(&pop[p]) {
+                dominated[q].push(p);
+                domination_count[p] = domination_count[p] + 1;
+            }
+        }
+    }
+
+    let mut current: Vec<usize> = (0..n).filter(|&p| domination_count[p] == 0).collect();
+    let mut rank = 0;
+    while !current.is_empty() {
+        let mut next = Vec::new();
+        for &p in &current {
+            pop[p].rank = rank;
+            for &q in &dominated[p] {
+                domination_count[q] = domination_count[q] - 1;
+                if domination_count[q] == 0 {
+                    next.push(q);
+                }
+            }
+        }
+        crowding_distance(pop, &current);
+        current = next;
+        rank = rank + 1;
+    }
+    pop.sort_by(|a, b| a.compare(b));
+}
+
+// Boundary members of a front get an infinite distance so that its extremes are always kept
+fn crowding_distance(pop: &mut Vec<Member>, front: &[usize]) {
+    for &p in front {
+        pop[p].crowding = 0.0;
+    }
+    let n_objectives = pop[front[0]].values.len();
+    let mut sorted = front.to_vec();
+    for o in 0..n_objectives {
+        sorted.sort_by(|&a, &b| pop[a].values[o].partial_cmp(&pop[b].values[o]).unwrap_or(Ordering::Equal));
+        let min = pop[sorted[0]].values[o];
+        let max = pop[sorted[sorted.len() - 1]].values[o];
+        pop[sorted[0]].crowding = f64::INFINITY;
+        pop[sorted[sorted.len() - 1]].crowding = f64::INFINITY;
+        if max <= min {
+            continue;
+        }
+        for k in 1..sorted.len().saturating_sub(1) {
+            let gap = (pop[sorted[k + 1]].values[o] - pop[sorted[k - 1]].values[o])/(max - min);
+            pop[sorted[k]].crowding = pop[sorted[k]].crowding + gap;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{Customer, Depot};
+
+    // A member with given objective values and violation, its genome doesn't matter
+    fn member(values: &[f64], violation: f64) -> Member {
+        let problem = Problem::new(vec![Depot::init(0.0, 0.0, 0, 10, 1)], vec![Customer::init(1.0, 0.0, 0, 1)]);
+        let genome = Genome::generate(vec![1], &problem, &SolverConfig::default());
+        Member{genome, values: values.to_vec(), violation, rank: 0, crowding: 0.0}
+    }
+
+    #[test]
+    fn constrained_domination() {
+        let feasible = member(&[5.0, 5.0], 0.0);
+        let slightly_infeasible = member(&[1.0, 1.0], 2.0);
+        let infeasible = member(&[0.0, 0.0], 3.0);
+        assert!(feasible.dominates(&slightly_infeasible));
+        assert!(!slightly_infeasible.dominates(&feasible));
+        assert!(slightly_infeasible.dominates(&infeasible));
+
+        // Equal violations fall back to Pareto dominance, and ties dominate neither way
+        assert!(member(&[1.0, 2.0], 3.0).dominates(&member(&[1.0, 3.0], 3.0)));
+        assert!(!member(&[1.0, 2.0], 0.0).dominates(&member(&[2.0, 1.0], 0.0)));
+        assert!(!member(&[1.0, 2.0], 0.0).dominates(&member(&[1.0, 2.0], 0.0)));
+    }
+
+    #[test]
+    fn non_dominated_sort() {
+        let mut pop = vec![
+            member(&[3.0, 3.0], 0.0),
+            member(&[0.0, 0.0], 10.0),
+            member(&[2.0, 2.0], 0.0),
+            member(&[1.0, 4.0], 0.0),
+            member(&[1.0, 1.0], 5.0),
+            member(&[2.0, 2.0], 0.0),
+            member(&[4.0, 1.0], 0.0),
+        ];
+        sort_population(&mut pop);
+        let ranks: Vec<(Vec<f64>, usize)> = pop.iter().map(|m| (m.values.clone(), m.rank)).collect();
+        // The tied members share the first front, after its two extremes
+        assert_eq!(ranks, vec![
+            (vec![1.0, 4.0], 0),
+            (vec![4.0, 1.0], 0),
+            (vec![2.0, 2.0], 0),
+            (vec![2.0, 2.0], 0),
+            (vec![3.0, 3.0], 1),
+            (vec![1.0, 1.0], 2),
+            (vec![0.0, 0.0], 3),
+        ]);
+    }
+
+    #[test]
+    fn crowding_distances() {
+        let mut pop = vec![member(&[1.0, 4.0], 0.0), member(&[2.0, 2.0], 0.0), member(&[9.0, 9.0], 0.0), member(&[2.0, 2.0], 0.0), member(&[4.0, 1.0], 0.0)];
+        crowding_distance(&mut pop, &[0, 1, 3, 4]);
+        let crowding: Vec<f64> = pop.iter().map(|m| m.crowding).collect();
+        // Between its neighbours in each objective, the first tied member sees the other one as a neighbour on one side
+        assert_eq!(crowding, vec![f64::INFINITY, 2.0/3.0, 0.0, 4.0/3.0, f64::INFINITY]);
+
+        // Without any spread only the ends of the front are kept for sure
+        let mut pop = vec![member(&[1.0, 1.0], 0.0), member(&[1.0, 1.0], 0.0), member(&[1.0, 1.0], 0.0)];
+        crowding_distance(&mut pop, &[0, 1, 2]);
+        let crowding: Vec<f64> = pop.iter().map(|m| m.crowding).collect();
+        assert_eq!(crowding, vec![f64::INFINITY, 0.0, f64::INFINITY]);
+    }
+}
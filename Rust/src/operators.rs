@@ -0,0 +1,200 @@
+use crate::assignment::borderline_customers;
+use crate::config::{OperatorSelection, SolverConfig};
+use crate::world::Problem;
+
+// Mutation operators, in the order of their probabilities
+pub const INSERT: usize = 0;
+pub const SWAP: usize = 1;
+pub const SCRAMBLE: usize = 2;
+pub const INTER_DEPOT: usize = 3;
+const MUTATIONS: [&str; 4] = ["insert", "swap", "scramble", "inter_depot"];
+
+// Crossover operators, in the order of their probabilities
+pub const ORDER1: usize = 0;
+pub const PMX: usize = 1;
+pub const EDGE_RECOMB: usize = 2;
+pub const BCRC: usize = 3;
+const CROSSOVERS: [&str; 4] = ["order1", "pmx", "edge_recomb", "bcrc"];
+
+// Probabilities of the operators of one kind. They start at the fractions of the configuration and, unless
+// the selection is fixed, follow the quality of each operator : the average reward of the children it made,
+// smoothed over the generations. Operators whose fraction is zero are never used.
+pub struct OperatorWeights {
+    names: [&'static str; 4],
+    enabled: [bool; 4],
+    probs: [f64; 4],
+    quality: [f64; 4],
+}
+
+impl OperatorWeights {
+    fn new(names: [&'static str; 4], fractions: [f64; 4]) -> OperatorWeights {
+        let mut enabled = [false; 4];
+        for (k, &f) in fractions.iter().enumerate() {
+            enabled[k] = f > 0.0;
+        }
+        OperatorWeights{names, enabled, probs: fractions, quality: [0.0; 4]}
+    }
+
+    // The operator of a draw in [0, 1), or none when the draw is past every operator
+    pub fn pick(&self, draw: f64) -> Option<usize> {
+        let mut total = 0.0;
+        for (k, &p) in self.probs.iter().enumerate() {
+            total = total + p;
+            if draw < total {
+                return Some(k)
+            }
+        }
+        None
+    }
+
+    // Moves the quality of every operator used in the generation towards its average reward, then the probabilities
+    // towards the qualities : in proportion to them with probability matching, or towards the best operator with
+    // adaptive pursuit. Every operator in use keeps at least min_operator_prob.
+    fn update(&mut self, rewards: &[(usize, f64)], config: &SolverConfig) {
+        let mut sums = [0.0; 4];
+        let mut counts = [0; 4];
+        for &(k, reward) in rewards {
+            sums[k] = sums[k] + reward;
+            counts[k] = counts[k] + 1;
+        }
+        for k in 0..4 {
+            if counts[k] > 0 {
+                self.quality[k] = self.quality[k] + config.adaptation_rate*(sums[k]/counts[k] as f64 - self.quality[k]);
+            }
+        }
+
+        let enabled: Vec<usize> = (0..4).filter(|&k| self.enabled[k]).collect();
+        let min = config.min_operator_prob;
+        let total_quality: f64 = enabled.iter().map(|&k| self.quality[k]).sum();
+        if total_quality <= 0.0 {
+            return
+        }
+        match config.operator_selection {
+            OperatorSelection::Fixed => {},
+            OperatorSelection::Matching => {
+                for &k in &enabled {
+                    self.probs[k] = min + (1.0 - enabled.len() as f64*min)*self.quality[k]/total_quality;
+                }
+            },
+            OperatorSelection::Pursuit => {
+                let best = *enabled.iter().max_by(|&&a, &&b| self.quality[a].partial_cmp(&self.quality[b]).unwrap_or(std::cmp::Ordering::Equal)).unwrap();
+                let max = 1.0 - (enabled.len() - 1) as f64*min;
+                for &k in &enabled {
+                    let target = if k == best {max} else {min};
+                    self.probs[k] = self.probs[k] + config.adaptation_rate*(target - self.probs[k]);
+                }
+            },
+        }
+    }
+
+    fn report(&self) -> Vec<(String, f64)> {
+        self.names.iter().zip(&self.probs).map(|(&name, &p)| (name.to_string(), p)).collect()
+    }
+}
+
+// Rewards of the operators that made some children
+#[derive(Default)]
+pub struct Credits {
+    pub mutation: Vec<(usize, f64)>,
+    pub crossover: Vec<(usize, f64)>,
+}
+
+impl Credits {
+    pub fn extend(&mut self, other: Credits) {
+        self.mutation.extend(other.mutation);
+        self.crossover.extend(other.crossover);
+    }
+}
+
+pub struct Operators {
+    pub mutation: OperatorWeights,
+    pub crossover: OperatorWeights,
+    // Customers the inter-depot mutation can move, with their close depots
+    pub borderline: Vec<(i64, Vec<usize>)>,
+}
+
+impl Operators {
+    pub fn new(problem: &Problem, config: &SolverConfig) -> Operators {
+        let borderline = if config.frac_inter_depot > 0.0 {borderline_customers(problem, config.borderline_margin)} else {Vec::new()};
+        Operators{
+            mutation: OperatorWeights::new(MUTATIONS, [config.frac_insert, config.frac_swap, config.frac_scramble, config.frac_inter_depot]),
+            crossover: OperatorWeights::new(CROSSOVERS, [config.frac_order1, config.frac_pmx, config.frac_edge_recomb, config.frac_bcrc]),
+            borderline,
+        }
+    }
+
+    // Adapts the probabilities to the children of a generation, they stay fixed otherwise
+    pub fn update(&mut self, credits: &Credits, config: &SolverConfig) {
+        if config.operator_selection != OperatorSelection::Fixed {
+            self.mutation.update(&credits.mutation, config);
+            self.crossover.update(&credits.crossover, config);
+        }
+    }
+
+    // Final probabilities of every operator, when they were adapted
+    pub fn report(&self, config: &SolverConfig) -> Vec<(String, f64)> {
+        if config.operator_selection == OperatorSelection::Fixed {
+            return Vec::new()
+        }
+        let mut weights = self.mutation.report();
+        weights.extend(self.crossover.report());
+        weights
+    }
+}
+
+// Relative fitness gain of a child, nothing when it is worse
+pub fn gain(before: f64, after: f64) -> f64 {
+    ((after - before)/before).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+    use rand::Rng;
+
+    // Generations of children made by operators drawn from the probabilities, the later operators being rewarded more
+    #[test]
+    fn adapted_probabilities_stay_a_distribution_over_the_enabled_operators() {
+        for &selection in [OperatorSelection::Matching, OperatorSelection::Pursuit].iter() {
+            let config = SolverConfig{operator_selection: selection, min_operator_prob: 0.1, ..SolverConfig::default()};
+            let mut weights = OperatorWeights::new(CROSSOVERS, [0.5, 0.3, 0.0, 0.2]);
+            let mut rng = random::master_rng(4);
+            for _ in 0..100 {
+                let mut rewards = Vec::new();
+                for _ in 0..50 {
+                    if let Some(k) = weights.pick(rng.gen_range(0.0, 1.0)) {
+                        rewards.push((k, k as f64*rng.gen_range(0.0, 0.1)));
+                    }
+                }
+                weights.update(&rewards, &config);
+                assert!((weights.probs.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+                for k in 0..4 {
+                    if weights.enabled[k] {
+                        assert!(weights.probs[k] >= config.min_operator_prob - 1e-9);
+                    }
+                    else {
+                        assert_eq!(weights.probs[k], 0.0);
+                    }
+                }
+            }
+            // The most rewarded operator ends up the most likely
+            assert!(weights.probs[BCRC] > weights.probs[PMX] && weights.probs[BCRC] > weights.probs[ORDER1]);
+        }
+    }
+
+    #[test]
+    fn fixed_probabilities_are_the_fractions() {
+        let config = SolverConfig::default();
+        let mut operators = Operators{
+            mutation: OperatorWeights::new(MUTATIONS, [config.frac_insert, config.frac_swap, config.frac_scramble, config.frac_inter_depot]),
+            crossover: OperatorWeights::new(CROSSOVERS, [config.frac_order1, config.frac_pmx, config.frac_edge_recomb, config.frac_bcrc]),
+            borderline: Vec::new(),
+        };
+        let credits = Credits{mutation: vec![(INSERT, 0.5), (SWAP, 0.1)], crossover: vec![(ORDER1, 0.5)]};
+        operators.update(&credits, &config);
+        assert_eq!(operators.mutation.probs, [config.frac_insert, config.frac_swap, config.frac_scramble, config.frac_inter_depot]);
+        assert_eq!(operators.crossover.probs, [config.frac_order1, config.frac_pmx, config.frac_edge_recomb, config.frac_bcrc]);
+        assert!(operators.report(&config).is_empty());
+    }
+}
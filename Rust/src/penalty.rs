@@ -0,0 +1,46 @@
+use crate::config::SolverConfig;
+use crate::solution::Violations;
+
+// Proportions of feasible individuals this close to the target leave the weights as they are
+const BAND: f64 = 0.05;
+const MIN_WEIGHT: f64 = 0.1;
+const MAX_WEIGHT: f64 = 1e6;
+
+// Adapts the load and duration weights of run, the configuration of the next generation, to the violations of the
+// population. A weight grows by config.penalty_factor while fewer than config.target_feasible of the individuals
+// respect its constraint, and shrinks by it while more do, unless the population has converged (its best individual
+// unchanged for config.penalty_convergence generations) : lighter weights would only let it drift back towards
+// infeasible solutions. Returns whether a weight changed.
+pub fn adapt_weights<'a, I: Iterator<Item = &'a Violations>>(run: &mut SolverConfig, violations: I, converged: bool, config: &SolverConfig) -> bool {
+    let mut total = 0;
+    let mut load_feasible = 0;
+    let mut duration_feasible = 0;
+    for v in violations {
+        total = total + 1;
+        if v.load == 0 {
+            load_feasible = load_feasible + 1;
+        }
+        if v.duration == 0.0 {
+            duration_feasible = duration_feasible + 1;
+        }
+    }
+    if total == 0 {
+        return false
+    }
+    let load = adapt(run.load_weight, load_feasible as f64/total as f64, converged, config);
+    let duration = adapt(run.duration_weight, duration_feasible as f64/total as f64, converged, config);
+    let changed = load != run.load_weight || duration != run.duration_weight;
+    run.load_weight = load;
+    run.duration_weight = duration;
+    changed
+}
+
+fn adapt(weight: f64, feasible: f64, converged: bool, config: &SolverConfig) -> f64 {
+    if feasible < config.target_feasible - BAND {
+        return (weight*config.penalty_factor).min(MAX_WEIGHT).max(weight)
+    }
+    if feasible > config.target_feasible + BAND && !converged {
+        return (weight/config.penalty_factor).max(MIN_WEIGHT).min(weight)
+    }
+    weight
+}
@@ -0,0 +1,26 @@
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+// Every random draw of a run derives from a single master seed. Tasks running in parallel get their
+// own generator, seeded from the master seed and their position, so the result doesn't depend on
+// which thread runs them or in which order.
+
+pub fn draw_seed() -> u64 {
+    thread_rng().gen()
+}
+
+pub fn master_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+pub fn task_rng(seed: u64, generation: usize, task: usize) -> StdRng {
+    StdRng::seed_from_u64(mix(mix(seed ^ 0x9e37_79b9_7f4a_7c15, generation as u64), task as u64))
+}
+
+// SplitMix64 step, so that neighbouring generations and tasks get unrelated seeds
+fn mix(state: u64, value: u64) -> u64 {
+    let mut z = state.wrapping_add(value.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,161 @@
+use std::ops::Add;
+
+use crate::config::{Objective, ParetoObjective};
+
+// How far a route goes over the limits of its depot, all zero for a feasible route.
+// Lateness sums how late the vehicle is at every customer and back at the depot.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Violations {
+    pub load: i64,
+    pub duration: f64,
+    pub lateness: f64,
+}
+
+impl Violations {
+    pub fn is_empty(&self) -> bool {
+        self.load == 0 && self.duration == 0.0 && self.lateness == 0.0
+    }
+
+    // Sum of every violation, the fitness function weights them separately
+    pub fn penalty(&self) -> f64 {
+        self.load as f64 + self.duration + self.lateness
+    }
+}
+
+impl Add for Violations {
+    type Output = Violations;
+
+    fn add(self, other: Violations) -> Violations {
+        Violations{load: self.load + other.load, duration: self.duration + other.duration, lateness: self.lateness + other.lateness}
+    }
+}
+
+// Scores of a customer order. The longest route is the one with the highest duration,
+// the imbalance is its difference with the shortest route that serves customers.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Evaluation {
+    pub total_cost: f64,
+    pub longest_route: f64,
+    pub vehicles_used: usize,
+    pub imbalance: f64,
+    pub violations: Violations,
+}
+
+// A vehicle tour. The depot and vehicle are indexes starting from 0, customers are numbered like in the instance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Route {
+    pub depot: usize,
+    pub vehicle: usize,
+    pub customers: Vec<usize>,
+    // Travel, service and waiting time
+    pub duration: f64,
+    pub waiting: f64,
+    pub distance: f64,
+    // Distance weighted by the vehicle type, plus its fixed cost when the route isn't empty
+    pub cost: f64,
+    pub load: i64,
+    pub violations: Violations,
+}
+
+impl Route {
+    pub fn empty(depot: usize, vehicle: usize) -> Route {
+        Route{depot, vehicle, customers: Vec::new(), duration: 0.0, waiting: 0.0, distance: 0.0, cost: 0.0, load: 0, violations: Violations::default()}
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.customers.is_empty()
+    }
+
+    pub fn is_feasible(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+// Best individual found by the solver, with one route per vehicle of every depot
+#[derive(Clone, Debug)]
+pub struct Solution {
+    pub routes: Vec<Route>,
+    pub total_distance: f64,
+    pub total_cost: f64,
+    pub longest_route: f64,
+    // Objective the solver minimised and its value for this solution
+    pub objective: Objective,
+    pub objective_value: f64,
+    // Values of SolverConfig::pareto_objectives, in the same order, for the solutions of a Pareto front
+    pub pareto_values: Vec<f64>,
+    pub penalty: f64,
+    // Set when SolverConfig::default_max_duration was applied to depots without a duration limit
+    pub default_max_duration: Option<i64>,
+    // Seed of the run, giving it again in SolverConfig::seed reproduces this solution
+    pub seed: u64,
+    // Distance and penalty of the best individual at each generation
+    pub best_distances: Vec<f64>,
+    pub best_penalties: Vec<f64>,
+    // Final probability of every mutation and crossover operator, when SolverConfig::operator_selection adapts them
+    pub operator_weights: Vec<(String, f64)>,
+    // Load and duration weights of each generation, when SolverConfig::adaptive_penalty adapts them
+    pub penalty_weights: Vec<(f64, f64)>,
+}
+
+impl Solution {
+    pub fn is_valid(&self) -> bool {
+        self.penalty == 0.0
+    }
+
+    pub fn depot_routes(&self, depot: usize) -> impl Iterator<Item = &Route> {
+        self.routes.iter().filter(move |r| r.depot == depot)
+    }
+
+    // Total cost on the first line, then one "depot vehicle duration load 0 customers... 0" line per route
+    pub fn result_text(&self) -> String {
+        let mut result_string = format!("{:.2}", self.total_cost);
+        for route in &self.routes {
+            let mut cus_list = String::from("0 ");
+            for c in &route.customers {
+                cus_list.push_str(format!("{} ", c).as_str());
+            }
+            cus_list.push('0');
+            result_string.push_str(format!("\n{:<3} {:<3} {:<7.2} {:<4} {}", route.depot+1, route.vehicle+1, route.duration, route.load, cus_list).as_str());
+        }
+        result_string
+    }
+
+    // One "solution objective_values... penalty" line per solution of a Pareto front, numbered from 1 like their result files
+    pub fn pareto_text(front: &[Solution], objectives: &[ParetoObjective]) -> String {
+        let mut text = String::from("# solution");
+        for o in objectives {
+            text.push_str(format!(" {}", o).as_str());
+        }
+        text.push_str(" penalty");
+        for (k, solution) in front.iter().enumerate() {
+            text.push_str(format!("\n{}", k + 1).as_str());
+            for v in &solution.pareto_values {
+                text.push_str(format!(" {:.2}", v).as_str());
+            }
+            text.push_str(format!(" {:.2}", solution.penalty).as_str());
+        }
+        text
+    }
+
+    // Best distances on the first line and best penalties on the second, then with an adaptive penalty the weights
+    // on two more lines. DrawResult.ipynb plots the first two, and the weights when they are there.
+    pub fn convergence_text(&self) -> String {
+        let mut bests = String::new();
+        let mut penalties = String::new();
+        for (b, p) in self.best_distances.iter().zip(&self.best_penalties) {
+            bests.push_str(format!("{:.2} ", b).as_str());
+            penalties.push_str(format!("{:.2} ", p).as_str());
+        }
+        if self.penalty_weights.is_empty() {
+            return format!("{}\n{}", bests, penalties)
+        }
+        // Then the load and duration weights of every generation
+        let mut loads = String::new();
+        let mut durations = String::new();
+        for (l, d) in &self.penalty_weights {
+            loads.push_str(format!("{:.2} ", l).as_str());
+            durations.push_str(format!("{:.2} ", d).as_str());
+        }
+        format!("{}\n{}\n{}\n{}", bests, penalties, loads, durations)
+    }
+}
@@ -0,0 +1,54 @@
+use crate::config::SolverConfig;
+use crate::gen_alg;
+use crate::nsga2;
+use crate::genome::Genome;
+use crate::decoder;
+use crate::solution::{Evaluation, Route, Solution};
+use crate::world::Problem;
+
+// Entry point of the library, runs the genetic algorithm on a problem
+pub struct Solver;
+
+impl Solver {
+    // The configuration is expected to have passed SolverConfig::validate
+    pub fn solve(problem: &Problem, config: &SolverConfig) -> Solution {
+        let (adjusted, default_max_duration) = Self::adjust(problem, config);
+        let mut solution = gen_alg::train(adjusted.as_ref().unwrap_or(problem), config);
+        solution.default_max_duration = default_max_duration;
+        solution
+    }
+
+    // Multi-objective run with NSGA-II over config.pareto_objectives, returns the solutions of the Pareto front
+    pub fn solve_pareto(problem: &Problem, config: &SolverConfig) -> Vec<Solution> {
+        let (adjusted, default_max_duration) = Self::adjust(problem, config);
+        let mut front = nsga2::train_pareto(adjusted.as_ref().unwrap_or(problem), config);
+        for solution in &mut front {
+            solution.default_max_duration = default_max_duration;
+        }
+        front
+    }
+
+    // Copy of the problem with the duration limit and rounding of the configuration, when they change it
+    fn adjust(problem: &Problem, config: &SolverConfig) -> (Option<Problem>, Option<i64>) {
+        let unlimited = problem.depots().iter().any(|d| !d.has_duration_limit());
+        let default_max_duration = config.default_max_duration.filter(|_| unlimited);
+
+        let mut adjusted = default_max_duration.map(|max_duration| problem.with_default_max_duration(max_duration));
+        if problem.rounding() != config.distance_rounding {
+            adjusted = Some(adjusted.as_ref().unwrap_or(problem).with_rounding(config.distance_rounding));
+        }
+        (adjusted, default_max_duration)
+    }
+
+    // Total cost, longest route and summed violations of a customer order, where 0 separates the routes of consecutive vehicles.
+    // The cost is the total distance unless the depots have vehicle types with other costs.
+    // This is the evaluation the genetic algorithm applies to every individual.
+    pub fn evaluate(problem: &Problem, customer_order: &[i64]) -> Evaluation {
+        Genome::evaluate(customer_order, problem)
+    }
+
+    // Routes of a customer order with their load, duration, distance and constraint violations
+    pub fn decode(problem: &Problem, customer_order: &[i64]) -> Vec<Route> {
+        decoder::decode(customer_order, problem)
+    }
+}
@@ -0,0 +1,214 @@
+use crate::config::SolverConfig;
+use crate::decoder::RouteBuilder;
+use crate::genome::Genome;
+use crate::world::Problem;
+
+// Routes of a split may exceed the load and duration limits of their vehicle by this factor, with a penalty,
+// so that tours that can't be split into feasible routes still get a fitness that leads towards them
+const MAX_EXCESS: f64 = 1.5;
+
+// Splits a giant tour into routes and returns them as a customer order with a zero between every vehicle.
+// The zeros of the tour separate the customers of consecutive depots, each depot's part is cut into routes
+// of its vehicles by Prins' shortest path algorithm.
+pub fn split(tour: &[i64], problem: &Problem, config: &SolverConfig) -> Vec<i64> {
+    let mut customer_order = Vec::with_capacity(tour.len() + problem.total_vehicles());
+    let parts: Vec<&[i64]> = tour.split(|&c| c == 0).collect();
+    debug_assert_eq!(parts.len(), problem.depots().len(), "a giant tour has a zero between the customers of consecutive depots");
+    for (depot, part) in parts.iter().enumerate() {
+        for (vehicle, route) in split_depot(part, depot, problem, config).iter().enumerate() {
+            if depot > 0 || vehicle > 0 {
+                customer_order.push(0);
+            }
+            customer_order.extend_from_slice(route);
+        }
+    }
+    customer_order
+}
+
+// Shortest path over the cuts of the tour, where the k-th arc of a path is the route of the depot's k-th vehicle
+// and may be skipped to leave that vehicle unused. Routes cost their cost plus the weighted penalty of their violations.
+// With vehicles of several types, the order of the fleet decides which types the routes of the tour go to.
+fn split_depot(tour: &[i64], depot: usize, problem: &Problem, config: &SolverConfig) -> Vec<Vec<i64>> {
+    let n = tour.len();
+    let fleet = problem.depots()[depot].fleet();
+    let mut types = Vec::new();
+    let mut first_vehicle = Vec::new();
+    for (t, vehicle_type) in fleet.iter().enumerate() {
+        first_vehicle.push(types.len());
+        for _ in 0..vehicle_type.count() {
+            types.push(t);
+        }
+    }
+
+    // Routes of every vehicle type, as (end, cost) for each start in the tour
+    let arcs: Vec<Vec<Vec<(usize, f64)>>> = first_vehicle.iter().map(|&vehicle| {
+        let vehicle_type = &fleet[types[vehicle]];
+        let max_load = MAX_EXCESS*vehicle_type.max_load() as f64;
+        let max_duration = MAX_EXCESS*vehicle_type.max_duration() as f64;
+        (0..n).map(|i| {
+            let mut route = RouteBuilder::new(problem, depot, vehicle);
+            let mut ends = Vec::new();
+            for (j, &c) in tour.iter().enumerate().skip(i) {
+                route.push(c);
+                let stats = route.stats();
+                if j > i && (stats.load as f64 > max_load || (vehicle_type.has_duration_limit() && stats.duration > max_duration)) {
+                    break;
+                }
+                ends.push((j + 1, stats.cost + Genome::weighted_penalty(&stats.violations, config)));
+            }
+            ends
+        }).collect()
+    }).collect();
+
+    // With a single vehicle type, the best split over an unlimited fleet is the answer when it needs few enough vehicles
+    if fleet.len() == 1 {
+        let mut cost = vec![f64::INFINITY; n + 1];
+        let mut previous = vec![0; n + 1];
+        let mut used = vec![0; n + 1];
+        cost[0] = 0.0;
+        for i in 0..n {
+            for &(j, c) in &arcs[0][i] {
+                if cost[i] + c < cost[j] {
+                    cost[j] = cost[i] + c;
+                    previous[j] = i;
+                    used[j] = used[i] + 1;
+                }
+            }
+        }
+        if used[n] <= types.len() {
+            let mut routes = vec![Vec::new(); types.len()];
+            let mut j = n;
+            for k in (0..used[n]).rev() {
+                routes[k] = tour[previous[j]..j].to_vec();
+                j = previous[j];
+            }
+            return routes
+        }
+    }
+
+    // cost[k][j] is the cheapest way to serve the first j customers with the first k vehicles
+    let mut cost = vec![vec![f64::INFINITY; n + 1]; types.len() + 1];
+    let mut previous = vec![(0..=n).collect::<Vec<usize>>(); types.len() + 1];
+    cost[0][0] = 0.0;
+    for (k, &t) in types.iter().enumerate() {
+        cost[k + 1] = cost[k].clone();
+        for i in 0..n {
+            if cost[k][i] == f64::INFINITY {
+                continue;
+            }
+            for &(j, c) in &arcs[t][i] {
+                if cost[k][i] + c < cost[k + 1][j] {
+                    cost[k + 1][j] = cost[k][i] + c;
+                    previous[k + 1][j] = i;
+                }
+            }
+        }
+    }
+
+    // When the vehicles can't serve the tour even so, the last one takes the rest of it from the furthest cut
+    // the others reach, and the penalty of its route leads the search towards tours that can be split
+    let vehicles = types.len();
+    let mut routes = vec![Vec::new(); vehicles];
+    if vehicles == 0 {
+        return routes
+    }
+    if cost[vehicles][n] == f64::INFINITY {
+        let i = (0..n).rev().find(|&i| cost[vehicles - 1][i] < f64::INFINITY).unwrap_or(0);
+        previous[vehicles][n] = i;
+    }
+    let mut j = n;
+    for k in (0..vehicles).rev() {
+        let i = previous[k + 1][j];
+        routes[k] = tour[i..j].to_vec();
+        j = i;
+    }
+    routes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::evaluate_route;
+    use crate::random;
+    use crate::world::{Customer, Depot};
+    use rand::prelude::*;
+
+    // Seven customers of load 10 around one depot whose vehicles carry 30
+    fn problem(vehicles: i64) -> Problem {
+        let mut rng = random::master_rng(7);
+        let customers = (0..7).map(|_| Customer::init(rng.gen_range(0.0, 50.0), rng.gen_range(0.0, 50.0), 0, 10)).collect();
+        Problem::new(vec![Depot::init(25.0, 25.0, 0, 30, vehicles)], customers)
+    }
+
+    fn score(route: &[i64], problem: &Problem, config: &SolverConfig) -> f64 {
+        if route.is_empty() {
+            return 0.0
+        }
+        let stats = evaluate_route(problem, 0, 0, route);
+        stats.cost + Genome::weighted_penalty(&stats.violations, config)
+    }
+
+    // Cheapest cut of the tour into at most as many routes as there are vehicles, by trying all of them
+    fn brute_force(tour: &[i64], problem: &Problem, config: &SolverConfig) -> f64 {
+        let n = tour.len();
+        let max_load = MAX_EXCESS*problem.depots()[0].vehicle_type(0).max_load() as f64;
+        let mut best = f64::INFINITY;
+        for cuts in 0..(1 << (n - 1)) {
+            let mut routes = Vec::new();
+            let mut start = 0;
+            for i in 1..=n {
+                if i == n || cuts & (1 << (i - 1)) != 0 {
+                    routes.push(&tour[start..i]);
+                    start = i;
+                }
+            }
+            let too_long = routes.iter().any(|r| r.len() > 1 && evaluate_route(problem, 0, 0, r).load as f64 > max_load);
+            if routes.len() <= problem.total_vehicles() && !too_long {
+                best = best.min(routes.iter().map(|r| score(r, problem, config)).sum());
+            }
+        }
+        best
+    }
+
+    fn check(vehicles: i64) {
+        let problem = problem(vehicles);
+        let config = SolverConfig::default();
+        for seed in 0..20 {
+            let mut tour: Vec<i64> = (1..=7).collect();
+            tour.shuffle(&mut random::master_rng(seed));
+            let routes = split_depot(&tour, 0, &problem, &config);
+            assert_eq!(routes.len(), vehicles as usize);
+            assert_eq!(routes.concat(), tour);
+            let cost: f64 = routes.iter().map(|r| score(r, &problem, &config)).sum();
+            assert!((cost - brute_force(&tour, &problem, &config)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn matches_brute_force_with_an_unlimited_fleet() {
+        check(7);
+    }
+
+    // Three vehicles can serve the customers within their load, two have to overload
+    #[test]
+    fn matches_brute_force_with_a_limited_fleet() {
+        check(3);
+        check(2);
+    }
+
+    #[test]
+    fn puts_a_zero_between_every_vehicle() {
+        let problem = Problem::new(vec![Depot::init(0.0, 0.0, 0, 30, 2), Depot::init(10.0, 0.0, 0, 30, 3)], problem(1).customers().to_vec());
+        let config = SolverConfig::default();
+        let order = split(&[1, 2, 3, 0, 4, 5, 6, 7], &problem, &config);
+        assert_eq!(order.iter().filter(|&&c| c == 0).count(), 4);
+        assert_eq!(order.iter().filter(|&&c| c != 0).count(), 7);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "a giant tour has a zero between the customers of consecutive depots")]
+    fn rejects_a_wrong_number_of_zeros() {
+        split(&[1, 2, 0, 3, 4, 0, 5, 6, 7], &problem(2), &SolverConfig::default());
+    }
+}
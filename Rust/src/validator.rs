@@ -0,0 +1,193 @@
+use serde::Serialize;
+
+use crate::decoder::evaluate_route;
+use crate::file_io::ResultFile;
+use crate::world::Problem;
+
+// The declared cost is written with two decimals
+const COST_TOLERANCE: f64 = 0.01;
+
+// Depots, vehicles and customers are numbered like in the instance and result files
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Issue {
+    UnknownDepot { line: usize, depot: usize },
+    UnknownVehicle { line: usize, depot: usize, vehicle: usize, vehicles: i64 },
+    DuplicateVehicle { line: usize, depot: usize, vehicle: usize },
+    UnknownCustomer { line: usize, customer: i64 },
+    DuplicateCustomer { customer: i64, visits: usize },
+    MissingCustomer { customer: i64 },
+    LoadExceeded { line: usize, depot: usize, vehicle: usize, load: i64, max_load: i64 },
+    DurationExceeded { line: usize, depot: usize, vehicle: usize, duration: f64, max_duration: i64 },
+    LateArrival { line: usize, depot: usize, vehicle: usize, lateness: f64 },
+    CostMismatch { declared: f64, computed: f64 },
+}
+
+// Values recomputed from the instance for a route of the result file
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RouteReport {
+    pub line: usize,
+    pub depot: usize,
+    pub vehicle: usize,
+    pub customers: Vec<i64>,
+    pub load: i64,
+    pub duration: f64,
+    pub waiting: f64,
+    pub distance: f64,
+    pub cost: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub declared_cost: f64,
+    pub computed_cost: f64,
+    pub routes: Vec<RouteReport>,
+    pub issues: Vec<Issue>,
+}
+
+// Checks a solution against the instance without trusting any value it declares but its routes
+pub fn validate(problem: &Problem, result: &ResultFile) -> ValidationReport {
+    let depots = problem.depots();
+    let n_customers = problem.customers().len();
+    let mut issues = Vec::new();
+    let mut visits = vec![0; n_customers];
+    let mut vehicles_used = vec![Vec::new(); depots.len()];
+    let mut routes = Vec::new();
+    let mut computed_cost = 0.0;
+
+    for route in &result.routes {
+        let (line, depot_number, vehicle_number) = (route.line, route.depot + 1, route.vehicle + 1);
+        let mut known = Vec::new();
+        for &c in &route.customers {
+            if c < 1 || c as usize > n_customers {
+                issues.push(Issue::UnknownCustomer{line, customer: c});
+            }
+            else {
+                visits[c as usize - 1] = visits[c as usize - 1] + 1;
+                known.push(c);
+            }
+        }
+
+        let depot = match depots.get(route.depot) {
+            None => {
+                issues.push(Issue::UnknownDepot{line, depot: depot_number});
+                continue;
+            },
+            Some(d) => d,
+        };
+        if route.vehicle >= depot.vehicles() as usize {
+            issues.push(Issue::UnknownVehicle{line, depot: depot_number, vehicle: vehicle_number, vehicles: depot.vehicles()});
+            continue;
+        }
+        // Empty routes count too, a vehicle makes at most one route
+        if vehicles_used[route.depot].contains(&route.vehicle) {
+            issues.push(Issue::DuplicateVehicle{line, depot: depot_number, vehicle: vehicle_number});
+        }
+        else {
+            vehicles_used[route.depot].push(route.vehicle);
+        }
+
+        let vehicle_type = depot.vehicle_type(route.vehicle);
+        let stats = evaluate_route(problem, route.depot, route.vehicle, &known);
+        if stats.violations.load > 0 {
+            issues.push(Issue::LoadExceeded{line, depot: depot_number, vehicle: vehicle_number, load: stats.load, max_load: vehicle_type.max_load()});
+        }
+        if stats.violations.duration > 0.0 {
+            issues.push(Issue::DurationExceeded{line, depot: depot_number, vehicle: vehicle_number, duration: stats.duration, max_duration: vehicle_type.max_duration()});
+        }
+        if stats.violations.lateness > 0.0 {
+            issues.push(Issue::LateArrival{line, depot: depot_number, vehicle: vehicle_number, lateness: stats.violations.lateness});
+        }
+        computed_cost = computed_cost + stats.cost;
+        routes.push(RouteReport{line, depot: depot_number, vehicle: vehicle_number, customers: known, load: stats.load, duration: stats.duration, waiting: stats.waiting, distance: stats.distance, cost: stats.cost});
+    }
+
+    for (i, &v) in visits.iter().enumerate() {
+        match v {
+            0 => issues.push(Issue::MissingCustomer{customer: i as i64 + 1}),
+            1 => (),
+            _ => issues.push(Issue::DuplicateCustomer{customer: i as i64 + 1, visits: v}),
+        }
+    }
+    if (result.cost - computed_cost).abs() > COST_TOLERANCE {
+        issues.push(Issue::CostMismatch{declared: result.cost, computed: computed_cost});
+    }
+
+    ValidationReport{valid: issues.is_empty(), declared_cost: result.cost, computed_cost, routes, issues}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_io::ResultRoute;
+    use crate::world::{Customer, Depot, TimeWindow};
+
+    // One depot with two vehicles, and two customers 5 and 10 away from it on a line
+    fn problem(max_duration: i64, max_load: i64, window: Option<TimeWindow>) -> Problem {
+        let second = Customer::init(6.0, 8.0, 0, 5);
+        let second = match window {
+            None => second,
+            Some(w) => second.with_window(w),
+        };
+        Problem::new(vec![Depot::init(0.0, 0.0, max_duration, max_load, 2)], vec![Customer::init(3.0, 4.0, 0, 5), second])
+    }
+
+    fn result(cost: f64, routes: &[(usize, usize, Vec<i64>)]) -> ResultFile {
+        let routes = routes.iter().enumerate()
+            .map(|(i, (depot, vehicle, customers))| ResultRoute{line: i + 2, depot: *depot, vehicle: *vehicle, duration: 0.0, load: 0, customers: customers.clone()})
+            .collect();
+        ResultFile{cost, routes}
+    }
+
+    fn issues(problem: &Problem, result: &ResultFile) -> Vec<Issue> {
+        validate(problem, result).issues
+    }
+
+    #[test]
+    fn accepts_a_valid_solution() {
+        let report = validate(&problem(0, 10, None), &result(20.0, &[(0, 0, vec![1, 2]), (0, 1, vec![])]));
+        assert!(report.valid);
+        assert_eq!(report.computed_cost, 20.0);
+    }
+
+    #[test]
+    fn reports_missing_and_duplicated_customers() {
+        let problem = problem(0, 10, None);
+        assert_eq!(issues(&problem, &result(10.0, &[(0, 0, vec![1])])), vec![Issue::MissingCustomer{customer: 2}]);
+        assert_eq!(issues(&problem, &result(40.0, &[(0, 0, vec![1, 2]), (0, 1, vec![2])])), vec![Issue::DuplicateCustomer{customer: 2, visits: 2}]);
+        assert_eq!(issues(&problem, &result(20.0, &[(0, 0, vec![1, 2, 3])])), vec![Issue::UnknownCustomer{line: 2, customer: 3}]);
+    }
+
+    #[test]
+    fn reports_overloads_and_duration_overruns() {
+        let solution = result(20.0, &[(0, 0, vec![1, 2])]);
+        assert_eq!(issues(&problem(0, 8, None), &solution), vec![Issue::LoadExceeded{line: 2, depot: 1, vehicle: 1, load: 10, max_load: 8}]);
+        assert_eq!(issues(&problem(15, 10, None), &solution), vec![Issue::DurationExceeded{line: 2, depot: 1, vehicle: 1, duration: 20.0, max_duration: 15}]);
+    }
+
+    #[test]
+    fn reports_late_arrivals() {
+        let solution = result(20.0, &[(0, 0, vec![1, 2])]);
+        let late = problem(0, 10, Some(TimeWindow{earliest: 0, latest: 6}));
+        assert_eq!(issues(&late, &solution), vec![Issue::LateArrival{line: 2, depot: 1, vehicle: 1, lateness: 4.0}]);
+        let on_time = problem(0, 10, Some(TimeWindow{earliest: 0, latest: 10}));
+        assert!(issues(&on_time, &solution).is_empty());
+    }
+
+    #[test]
+    fn reports_unknown_and_duplicate_vehicles() {
+        let problem = problem(0, 10, None);
+        assert_eq!(issues(&problem, &result(0.0, &[(0, 2, vec![1, 2])])), vec![Issue::UnknownVehicle{line: 2, depot: 1, vehicle: 3, vehicles: 2}]);
+        assert_eq!(issues(&problem, &result(0.0, &[(1, 0, vec![1, 2])])), vec![Issue::UnknownDepot{line: 2, depot: 2}]);
+        assert_eq!(issues(&problem, &result(20.0, &[(0, 0, vec![]), (0, 0, vec![1, 2])])), vec![Issue::DuplicateVehicle{line: 3, depot: 1, vehicle: 1}]);
+        assert_eq!(issues(&problem, &result(20.0, &[(0, 0, vec![1]), (0, 0, vec![2])])).len(), 2);
+    }
+
+    #[test]
+    fn reports_a_cost_mismatch() {
+        let problem = problem(0, 10, None);
+        assert_eq!(issues(&problem, &result(25.0, &[(0, 0, vec![1, 2])])), vec![Issue::CostMismatch{declared: 25.0, computed: 20.0}]);
+        assert!(issues(&problem, &result(20.004, &[(0, 0, vec![1, 2])])).is_empty());
+    }
+}
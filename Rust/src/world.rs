@@ -1,98 +1,307 @@
-pub struct Customer {
-    x: i64,
-    y: i64,
-    duration: i64,
-    load: i64,
-}
-
-impl Customer {
-    pub fn dist(&self, x: i64, y: i64) -> i64 {
-        (((x - self.x)*(x - self.x) + (y - self.y)*(y - self.y)) as f64).sqrt() as i64
-    }
-
-    pub fn _dist_cust(&self, cust: &Customer) -> i64 {
-        self.dist(cust.x, cust.y)
-    }
-
-    pub fn _dist_dep(&self, dep: &Depot) -> i64 {
-        self.dist(dep.x, dep.y)
-    }
-
-    pub fn x(&self) -> i64 {
-        self.x
-    }
-
-    pub fn y(&self) -> i64 {
-        self.y
-    }
-
-    pub fn duration(&self) -> i64 {
-        self.duration
-    }
-
-    pub fn load(&self) -> i64 {
-        self.load
-    }
-
-    pub fn init(x: i64, y: i64, duration: i64, load: i64) -> Customer {
-        Customer{x, y, duration, load}
-    }
-}
-
-pub struct Depot {
-    x: i64,
-    y: i64,
-    max_duration: i64,
-    max_load: i64,
-    vehicles: i64,
-}
-
-impl Depot {
-    pub fn dist(&self, x: i64, y: i64) -> i64 {
-        (((x - self.x)*(x - self.x) + (y - self.y)*(y - self.y)) as f64).sqrt() as i64
-    }
-
-    pub fn over_duration(&self, dur: i64) -> bool {
-        self.max_duration != 0 && dur > self.max_duration
-    }
-
-    pub fn over_load(&self, load: i64) -> bool {
-        load > self.max_load
-    }
-
-    pub fn _over_limits(&self, load: i64, dur: i64) -> bool {
-        self.over_duration(dur) || self.over_load(load)
-    }
-
-    pub fn _dist_dep(&self, dep: &Depot) -> i64 {
-        self.dist(dep.x, dep.y)
-    }
-
-    pub fn _dist_cust(&self, cust: &Customer) -> i64 {
-        self.dist(cust.x, cust.y)
-    }
-
-    pub fn x(&self) -> i64 {
-        self.x
-    }
-
-    pub fn y(&self) -> i64 {
-        self.y
-    }
-
-    pub fn max_duration(&self) -> i64 {
-        self.max_duration
-    }
-
-    pub fn max_load(&self) -> i64 {
-        self.max_load
-    }
-
-    pub fn vehicles(&self) -> i64 {
-        self.vehicles
-    }
-
-    pub fn init(x: i64, y: i64, max_duration: i64, max_load: i64, vehicles: i64) -> Depot {
-        Depot{x, y, max_duration, max_load, vehicles}
-    }
-}
\ No newline at end of file
+use serde::{Deserialize, Serialize};
+
+// How the Euclidean distance between two nodes is turned into the cost of a leg
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rounding {
+    Truncate,
+    Round,
+    #[default]
+    Exact,
+}
+
+impl Rounding {
+    pub fn apply(self, dist: f64) -> f64 {
+        match self {
+            Rounding::Truncate => dist.trunc(),
+            Rounding::Round => dist.round(),
+            Rounding::Exact => dist,
+        }
+    }
+}
+
+// Service at a customer, or the return of vehicles to a depot, should happen between earliest and latest.
+// Arriving early means waiting, arriving late is penalised.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeWindow {
+    pub earliest: i64,
+    pub latest: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Customer {
+    x: f64,
+    y: f64,
+    duration: i64,
+    load: i64,
+    window: Option<TimeWindow>,
+}
+
+impl Customer {
+    pub fn dist(&self, x: f64, y: f64) -> f64 {
+        ((x - self.x)*(x - self.x) + (y - self.y)*(y - self.y)).sqrt()
+    }
+
+    pub fn _dist_cust(&self, cust: &Customer) -> f64 {
+        self.dist(cust.x, cust.y)
+    }
+
+    pub fn _dist_dep(&self, dep: &Depot) -> f64 {
+        self.dist(dep.x, dep.y)
+    }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    pub fn duration(&self) -> i64 {
+        self.duration
+    }
+
+    pub fn load(&self) -> i64 {
+        self.load
+    }
+
+    pub fn window(&self) -> Option<TimeWindow> {
+        self.window
+    }
+
+    pub fn init(x: f64, y: f64, duration: i64, load: i64) -> Customer {
+        Customer{x, y, duration, load, window: None}
+    }
+
+    pub fn with_window(mut self, window: TimeWindow) -> Customer {
+        self.window = Some(window);
+        self
+    }
+}
+
+// Vehicles of a depot sharing the same limits and costs. A route costs fixed_cost if it serves any customer,
+// plus distance_cost per unit of distance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VehicleType {
+    max_duration: i64,
+    max_load: i64,
+    fixed_cost: f64,
+    distance_cost: f64,
+    count: i64,
+}
+
+impl VehicleType {
+    // A maximum duration of 0 means routes of these vehicles have no duration limit
+    pub fn has_duration_limit(&self) -> bool {
+        self.max_duration != 0
+    }
+
+    pub fn over_duration(&self, dur: f64) -> bool {
+        self.max_duration != 0 && dur > self.max_duration as f64
+    }
+
+    pub fn over_load(&self, load: i64) -> bool {
+        load > self.max_load
+    }
+
+    pub fn route_cost(&self, distance: f64, empty: bool) -> f64 {
+        if empty {0.0} else {self.fixed_cost + self.distance_cost*distance}
+    }
+
+    pub fn max_duration(&self) -> i64 {
+        self.max_duration
+    }
+
+    pub fn max_load(&self) -> i64 {
+        self.max_load
+    }
+
+    pub fn fixed_cost(&self) -> f64 {
+        self.fixed_cost
+    }
+
+    pub fn distance_cost(&self) -> f64 {
+        self.distance_cost
+    }
+
+    pub fn count(&self) -> i64 {
+        self.count
+    }
+
+    pub fn init(max_duration: i64, max_load: i64, fixed_cost: f64, distance_cost: f64, count: i64) -> VehicleType {
+        VehicleType{max_duration, max_load, fixed_cost, distance_cost, count}
+    }
+}
+
+// Vehicles of a depot are numbered through its fleet : the first vehicle type gives the first vehicles, and so on
+#[derive(Clone, Debug)]
+pub struct Depot {
+    x: f64,
+    y: f64,
+    fleet: Vec<VehicleType>,
+    vehicles: i64,
+    opening: Option<TimeWindow>,
+}
+
+impl Depot {
+    pub fn dist(&self, x: f64, y: f64) -> f64 {
+        ((x - self.x)*(x - self.x) + (y - self.y)*(y - self.y)).sqrt()
+    }
+
+    // True when every vehicle of the depot has a duration limit
+    pub fn has_duration_limit(&self) -> bool {
+        self.fleet.iter().all(|v| v.has_duration_limit())
+    }
+
+    pub fn _dist_dep(&self, dep: &Depot) -> f64 {
+        self.dist(dep.x, dep.y)
+    }
+
+    pub fn _dist_cust(&self, cust: &Customer) -> f64 {
+        self.dist(cust.x, cust.y)
+    }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    pub fn fleet(&self) -> &[VehicleType] {
+        &self.fleet
+    }
+
+    pub fn vehicle_type(&self, vehicle: usize) -> &VehicleType {
+        let mut first = 0;
+        for vehicle_type in &self.fleet {
+            first = first + vehicle_type.count() as usize;
+            if vehicle < first {
+                return vehicle_type
+            }
+        }
+        panic!("Wrong vehicle number : {}", vehicle)
+    }
+
+    pub fn vehicles(&self) -> i64 {
+        self.vehicles
+    }
+
+    // Vehicles leave at the start of the opening hours and should be back before they end
+    pub fn opening(&self) -> Option<TimeWindow> {
+        self.opening
+    }
+
+    // A depot with identical vehicles, whose cost is their distance
+    pub fn init(x: f64, y: f64, max_duration: i64, max_load: i64, vehicles: i64) -> Depot {
+        Depot{x, y, fleet: vec![VehicleType::init(max_duration, max_load, 0.0, 1.0, vehicles)], vehicles, opening: None}
+    }
+
+    pub fn with_opening(mut self, opening: TimeWindow) -> Depot {
+        self.opening = Some(opening);
+        self
+    }
+
+    pub fn with_fleet(mut self, fleet: Vec<VehicleType>) -> Depot {
+        self.vehicles = fleet.iter().map(|v| v.count()).sum();
+        self.fleet = fleet;
+        self
+    }
+}
+
+// A multi-depot instance. Customers are numbered from 1 in genomes and solutions, like in the data files.
+// Distances are computed once between every pair of nodes : customer c is node c - 1 and depot d is node n_customers + d.
+#[derive(Clone, Debug)]
+pub struct Problem {
+    depots: Vec<Depot>,
+    customers: Vec<Customer>,
+    rounding: Rounding,
+    distances: Vec<f64>,
+}
+
+impl Problem {
+    pub fn new(depots: Vec<Depot>, customers: Vec<Customer>) -> Problem {
+        Self::with_rounding_policy(depots, customers, Rounding::default())
+    }
+
+    pub fn with_rounding_policy(depots: Vec<Depot>, customers: Vec<Customer>, rounding: Rounding) -> Problem {
+        let coords: Vec<(f64, f64)> = customers.iter().map(|c| (c.x(), c.y()))
+            .chain(depots.iter().map(|d| (d.x(), d.y())))
+            .collect();
+        let mut distances = Vec::with_capacity(coords.len()*coords.len());
+        for &(x1, y1) in &coords {
+            for &(x2, y2) in &coords {
+                distances.push(rounding.apply(((x2 - x1)*(x2 - x1) + (y2 - y1)*(y2 - y1)).sqrt()));
+            }
+        }
+        Problem{depots, customers, rounding, distances}
+    }
+
+    // Copy of the problem with its distances rounded according to another policy
+    pub fn with_rounding(&self, rounding: Rounding) -> Problem {
+        Self::with_rounding_policy(self.depots.clone(), self.customers.clone(), rounding)
+    }
+
+    pub fn rounding(&self) -> Rounding {
+        self.rounding
+    }
+
+    pub fn n_nodes(&self) -> usize {
+        self.customers.len() + self.depots.len()
+    }
+
+    pub fn customer_node(number: i64) -> usize {
+        (number - 1) as usize
+    }
+
+    pub fn depot_node(&self, depot: usize) -> usize {
+        self.customers.len() + depot
+    }
+
+    pub fn distance(&self, from: usize, to: usize) -> f64 {
+        self.distances[from*self.n_nodes() + to]
+    }
+
+    pub fn depots(&self) -> &[Depot] {
+        &self.depots
+    }
+
+    pub fn customers(&self) -> &[Customer] {
+        &self.customers
+    }
+
+    pub fn customer(&self, number: i64) -> &Customer {
+        match self.customers.get((number - 1) as usize) {
+            None => panic!("Wrong customer number : {}", number),
+            Some(cust) => cust,
+        }
+    }
+
+    pub fn total_vehicles(&self) -> usize {
+        self.depots.iter().map(|d| d.vehicles() as usize).sum()
+    }
+
+    // Copy of the problem where vehicles without a duration limit get max_duration instead
+    pub fn with_default_max_duration(&self, max_duration: i64) -> Problem {
+        let mut problem = self.clone();
+        for depot in problem.depots.iter_mut() {
+            for vehicle_type in depot.fleet.iter_mut().filter(|v| !v.has_duration_limit()) {
+                vehicle_type.max_duration = max_duration;
+            }
+        }
+        problem
+    }
+
+    // Copy of the problem where the depots with a non empty fleet get these vehicles instead of their own
+    pub fn with_fleets(&self, fleets: Vec<Vec<VehicleType>>) -> Problem {
+        let mut problem = self.clone();
+        for (depot, fleet) in problem.depots.iter_mut().zip(fleets) {
+            if !fleet.is_empty() {
+                *depot = depot.clone().with_fleet(fleet);
+            }
+        }
+        problem
+    }
+}